@@ -0,0 +1,12 @@
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+@group(2) @binding(0) var<uniform> darkness: f32;
+@group(2) @binding(1) var light_mask: texture_2d<f32>;
+@group(2) @binding(2) var light_mask_sampler: sampler;
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    // The mask is black where there's no light at all, and white where it's fully lit.
+    let light = textureSample(light_mask, light_mask_sampler, mesh.uv).r;
+    return vec4<f32>(0., 0., 0., darkness * (1. - light));
+}
@@ -1,37 +1,46 @@
 #import bevy_sprite::mesh2d_vertex_output::VertexOutput
 
-const MESH_DIMENSIONS: vec2<f32> = vec2<f32>(2560., 1440.);
+// These must match the `Brush` enum in `level.rs`.
+const BRUSH_NONE: u32 = 0u;
+const BRUSH_ERASE: u32 = 1u;
+const BRUSH_PAINT: u32 = 2u;
 
 @group(2) @binding(0) var<uniform> cursor_position: vec2<f32>;
 @group(2) @binding(1) var terrain_texture: texture_2d<f32>;
 @group(2) @binding(2) var terrain_texture_sampler: sampler;
 @group(2) @binding(3) var mask_texture: texture_2d<f32>;
 @group(2) @binding(4) var mask_texture_sampler: sampler;
+@group(2) @binding(5) var<uniform> brush: u32;
+@group(2) @binding(6) var<uniform> brush_color: vec4<f32>;
+@group(2) @binding(7) var<uniform> brush_radius: f32;
+@group(2) @binding(8) var<uniform> mesh_dimensions: vec2<f32>;
 
 @fragment
 fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
     var terrain_color = textureSample(terrain_texture, terrain_texture_sampler, mesh.uv);
-    if terrain_color.a == 0. {
-        // Exit early, we don't need to do anything else. Note that this might not always be true,
-        // if we end up introducing mechanics by which empty space can be "filled in". For example,
-        // could we implement builders this way?
+    if brush == BRUSH_NONE || (terrain_color.a == 0. && brush != BRUSH_PAINT) {
+        // Exit early, we don't need to do anything else. Only painting can fill in empty space,
+        // which is how the editor adds terrain. Could we implement builders this way too?
         return terrain_color;
     }
 
     // Normalise pos to dimensions of underlying mesh (NOT window dimensions).
-    let npos = cursor_position / MESH_DIMENSIONS;
+    let npos = cursor_position / mesh_dimensions;
     let diff = mesh.uv - npos;
     // Scaling the diff by aspect ratio avoids the "squashed circle" problem.
-    let scaled_diff = vec2<f32>(diff.x * (MESH_DIMENSIONS.x / MESH_DIMENSIONS.y), diff.y);
+    let scaled_diff = vec2<f32>(diff.x * (mesh_dimensions.x / mesh_dimensions.y), diff.y);
     let distance = length(scaled_diff);
 
-    // Compare with the radius of the 20px mask texture, converted to UV via the smaller of the mesh
-    // dimensions.
-    if distance < (10. / MESH_DIMENSIONS.y) {
+    // Compare with the brush radius, converted to UV via the smaller of the mesh dimensions.
+    if distance < (brush_radius / mesh_dimensions.y) {
         // Convert NDC value to UV for mask texture sampling.
         let mask_uv = (diff + vec2<f32>(1.0)) / 2.0;
         var mask_color = textureSample(mask_texture, mask_texture_sampler, mask_uv);
-        terrain_color.a = mask_color.a;
+        if brush == BRUSH_PAINT {
+            terrain_color = mix(brush_color, terrain_color, mask_color.a);
+        } else {
+            terrain_color.a = mask_color.a;
+        }
     }
 
     return terrain_color;
@@ -1,13 +1,17 @@
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
 
-use crate::screens::Screen;
+use crate::{
+    game::{animation::sheet::AnimationSheet, level::definition::LevelDefinition},
+    screens::Screen,
+};
 
 pub fn plugin(app: &mut App) {
     app.add_loading_state(
         LoadingState::new(Screen::Loading)
             .continue_to_state(Screen::Title)
             .load_collection::<Characters>()
+            .load_collection::<Hazards>()
             .load_collection::<Levels>()
             .load_collection::<Masks>(),
     );
@@ -15,16 +19,22 @@ pub fn plugin(app: &mut App) {
 
 #[derive(AssetCollection, Resource)]
 pub struct Levels {
-    #[asset(path = "textures/level.png")]
-    pub level: Handle<Image>,
+    #[asset(path = "levels/first.level.ron")]
+    pub first: Handle<LevelDefinition>,
     #[asset(path = "textures/blank.png")]
     pub blank: Handle<Image>,
 }
 
 #[derive(AssetCollection, Resource)]
 pub struct Characters {
-    #[asset(path = "textures/yup.png")]
-    pub yup: Handle<Image>,
+    #[asset(path = "animations/yup.anim.ron")]
+    pub yup: Handle<AnimationSheet>,
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct Hazards {
+    #[asset(path = "animations/hazards.anim.ron")]
+    pub sheet: Handle<AnimationSheet>,
 }
 
 #[derive(AssetCollection, Resource)]
@@ -0,0 +1,339 @@
+use bevy::{
+    audio::{DefaultSpatialScale, SpatialScale, Volume},
+    prelude::*,
+};
+
+use crate::{
+    MainCamera,
+    assets::Levels,
+    config::Config,
+    game::{Game, level::definition::LevelDefinition},
+    screens::Screen,
+};
+
+// How long one track takes to fade into the next.
+const CROSSFADE_SECS: f32 = 1.5;
+// How loud music plays while the game is paused, relative to normal.
+const DUCKED_VOLUME: f32 = 0.3;
+// Higher values duck and unduck faster.
+const DUCKING_DECAY_RATE: f32 = 6.;
+// Played in levels that don't ask for anything else.
+const LEVEL_MUSIC_PATH: &str = "audio/music/level.ogg";
+const TITLE_MUSIC_PATH: &str = "audio/music/title.ogg";
+
+pub fn plugin(app: &mut App) {
+    app.add_event::<PlaySfx>();
+    app.init_resource::<Ducking>();
+    app.init_resource::<MissingMusic>();
+    app.init_resource::<MusicOverride>();
+    app.init_resource::<SfxHandles>();
+    app.add_systems(
+        Update,
+        (
+            choose_music,
+            drop_missing_music,
+            crossfade_music,
+            duck_music,
+            apply_music_volume,
+            fit_listener_to_view,
+            play_sfx,
+        )
+            .chain(),
+    );
+}
+
+/// Sound effects, grouped by what's happening rather than which file is played.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Sfx {
+    Dig,
+    Rescue,
+    SkillAssigned,
+    Splat,
+}
+
+impl Sfx {
+    const ALL: [Self; 4] = [Self::Dig, Self::Rescue, Self::SkillAssigned, Self::Splat];
+
+    /// The most voices of this sound that can play at once. With a hundred Yups about, any more
+    /// just add up to noise (and clipping).
+    fn max_voices(&self) -> usize {
+        match self {
+            // Only ever comes from the cursor.
+            Self::Dig => 1,
+            Self::Rescue | Self::Splat => 6,
+            Self::SkillAssigned => 2,
+        }
+    }
+
+    fn path(&self) -> &'static str {
+        match self {
+            Self::Dig => "audio/sfx/dig.ogg",
+            Self::Rescue => "audio/sfx/rescue.ogg",
+            Self::SkillAssigned => "audio/sfx/skill-assigned.ogg",
+            Self::Splat => "audio/sfx/splat.ogg",
+        }
+    }
+}
+
+/// Send this to play a sound effect.
+#[derive(Event, Debug)]
+pub struct PlaySfx {
+    /// Where in the level the sound comes from. Sounds with a position are panned and get quieter
+    /// the further they are from the middle of the view, others play the same wherever the camera
+    /// is.
+    pub position: Option<Vec2>,
+    pub sfx: Sfx,
+}
+
+impl PlaySfx {
+    pub fn new(sfx: Sfx) -> Self {
+        Self {
+            position: None,
+            sfx,
+        }
+    }
+
+    pub fn at(mut self, position: Vec2) -> Self {
+        self.position = Some(position);
+        self
+    }
+}
+
+/// A playing sound effect, despawned once it's finished.
+#[derive(Component, Debug)]
+struct SfxVoice(Sfx);
+
+// Sound effects are loaded up front, so they're ready the first time they're needed. They aren't
+// part of the loading state though: a missing sound shouldn't stop anyone playing.
+#[derive(Resource, Debug)]
+struct SfxHandles(Vec<(Sfx, Handle<AudioSource>)>);
+
+impl FromWorld for SfxHandles {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self(
+            Sfx::ALL
+                .into_iter()
+                .map(|sfx| (sfx, assets.load(sfx.path())))
+                .collect(),
+        )
+    }
+}
+
+/// Music to play instead of the usual track for the screen, such as when a level script changes
+/// the mood. Cleared whenever the screen changes.
+#[derive(Resource, Debug, Default, PartialEq)]
+pub struct MusicOverride(pub Option<String>);
+
+/// Music that couldn't be loaded, so isn't tried again.
+#[derive(Resource, Debug, Default)]
+struct MissingMusic(Vec<String>);
+
+/// A music track. Only one plays at a time, other than while crossfading.
+#[derive(Component, Debug)]
+struct MusicTrack {
+    path: String,
+    /// From 0 (silent) to 1 (full volume).
+    fade: f32,
+    fading_out: bool,
+}
+
+/// How much quieter music currently is, from 0 (silent) to 1 (not ducked at all).
+#[derive(Resource, Debug, Deref, DerefMut)]
+struct Ducking(f32);
+
+impl Default for Ducking {
+    fn default() -> Self {
+        Self(1.)
+    }
+}
+
+fn music_for_screen(
+    screen: &Screen,
+    definitions: &Assets<LevelDefinition>,
+    levels: Option<&Levels>,
+) -> Option<String> {
+    match screen {
+        Screen::Title => Some(TITLE_MUSIC_PATH.to_string()),
+        Screen::InGame | Screen::Intro => {
+            let definition = levels.and_then(|levels| definitions.get(&levels.first));
+            let music = definition.and_then(|definition| definition.music.clone());
+            Some(music.unwrap_or_else(|| LEVEL_MUSIC_PATH.to_string()))
+        }
+        // The editor is quiet, like the splash and loading screens.
+        Screen::Editor | Screen::Loading | Screen::Splash => None,
+    }
+}
+
+// Starts the track for the current screen, fading out whatever was playing before.
+fn choose_music(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    definitions: Res<Assets<LevelDefinition>>,
+    levels: Option<Res<Levels>>,
+    missing: Res<MissingMusic>,
+    mut music_override: ResMut<MusicOverride>,
+    mut tracks: Query<&mut MusicTrack>,
+    screen: Res<State<Screen>>,
+) {
+    if screen.is_changed() {
+        music_override.set_if_neq(MusicOverride(None));
+    } else if !music_override.is_changed() {
+        return;
+    }
+
+    let wanted = music_override
+        .0
+        .clone()
+        .or_else(|| music_for_screen(screen.get(), &definitions, levels.as_deref()));
+    let mut already_playing = false;
+    for mut track in &mut tracks {
+        if Some(&track.path) == wanted.as_ref() {
+            // Carry on, even if it had started to fade out.
+            track.fading_out = false;
+            already_playing = true;
+        } else {
+            track.fading_out = true;
+        }
+    }
+
+    let path = match wanted {
+        Some(path) if !already_playing && !missing.0.contains(&path) => path,
+        _ => return,
+    };
+    commands.spawn((
+        Name::new("Music"),
+        AudioPlayer::new(asset_server.load(&path)),
+        MusicTrack {
+            path,
+            fade: 0.,
+            fading_out: false,
+        },
+        // Silent to start with, see `apply_music_volume`.
+        PlaybackSettings::LOOP.with_volume(Volume::ZERO),
+    ));
+}
+
+// Tracks whose file is missing never start playing, so make way for the next one rather than
+// waiting on them forever.
+fn drop_missing_music(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut missing: ResMut<MissingMusic>,
+    tracks: Query<(Entity, &AudioPlayer, &MusicTrack), Without<AudioSink>>,
+) {
+    for (entity, player, track) in &tracks {
+        if asset_server.load_state(&player.0).is_failed() {
+            warn!("Could not play music {}", track.path);
+            missing.0.push(track.path.clone());
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn crossfade_music(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut tracks: Query<(Entity, &mut MusicTrack)>,
+) {
+    let step = time.delta_secs() / CROSSFADE_SECS;
+    for (entity, mut track) in &mut tracks {
+        if track.fading_out {
+            track.fade -= step;
+            if track.fade <= 0. {
+                commands.entity(entity).despawn_recursive();
+            }
+        } else {
+            track.fade = (track.fade + step).min(1.);
+        }
+    }
+}
+
+fn duck_music(game: Option<Res<State<Game>>>, mut ducking: ResMut<Ducking>, time: Res<Time<Real>>) {
+    let paused = game.is_some_and(|game| *game.get() == Game::Paused);
+    let target = if paused { DUCKED_VOLUME } else { 1. };
+    ducking
+        .0
+        .smooth_nudge(&target, DUCKING_DECAY_RATE, time.delta_secs());
+}
+
+// Changing the global volume only affects sounds played afterwards, so music, which plays for a long
+// time, has its volume kept up to date here instead.
+fn apply_music_volume(
+    config: Res<Config>,
+    ducking: Res<Ducking>,
+    tracks: Query<(&MusicTrack, &AudioSink)>,
+) {
+    let volume = config.audio.master_volume * config.audio.music_volume * **ducking;
+    for (track, sink) in &tracks {
+        sink.set_volume(volume * track.fade.max(0.));
+    }
+}
+
+// Keeps positional sounds in proportion to what's on screen however far the camera is zoomed out.
+// Sounds in the middle of the view play at full volume, and at the edges they're panned hard to
+// one side. Beyond that, they fade away with distance.
+fn fit_listener_to_view(
+    camera: Single<(&OrthographicProjection, &mut SpatialListener), With<MainCamera>>,
+    mut scale: ResMut<DefaultSpatialScale>,
+) {
+    let (projection, mut listener) = camera.into_inner();
+    let half_width = projection.area.width() / 2.;
+    if half_width <= 0. {
+        return;
+    }
+
+    // Spatial audio treats a distance of 1 as full volume, and attenuates beyond that.
+    let wanted_scale = SpatialScale::new_2d(1. / half_width);
+    if scale.0.0 != wanted_scale.0 {
+        scale.0 = wanted_scale;
+    }
+    let ear_offset = Vec3::X * half_width / 2.;
+    if listener.right_ear_offset != ear_offset {
+        listener.left_ear_offset = -ear_offset;
+        listener.right_ear_offset = ear_offset;
+    }
+}
+
+fn play_sfx(
+    mut commands: Commands,
+    config: Res<Config>,
+    handles: Res<SfxHandles>,
+    mut requests: EventReader<PlaySfx>,
+    sources: Res<Assets<AudioSource>>,
+    voices: Query<&SfxVoice>,
+) {
+    let playing = |sfx: Sfx| voices.iter().filter(|v| v.0 == sfx).count();
+    let mut started = vec![];
+    for PlaySfx { position, sfx } in requests.read() {
+        // Count what was started this frame too, as those voices haven't been spawned yet.
+        let count = playing(*sfx) + started.iter().filter(|s| *s == sfx).count();
+        if count >= sfx.max_voices() {
+            continue;
+        }
+        // Sounds that are missing, or haven't finished loading, are left out rather than played
+        // late (or never, leaving a voice that can't be used by anything else).
+        let Some((_, handle)) = handles
+            .0
+            .iter()
+            .find(|(s, h)| s == sfx && sources.contains(h))
+        else {
+            continue;
+        };
+
+        started.push(*sfx);
+        let mut voice = commands.spawn((
+            Name::new("Sound Effect"),
+            SfxVoice(*sfx),
+            AudioPlayer::new(handle.clone()),
+            // The global volume takes care of the master volume.
+            PlaybackSettings::DESPAWN
+                .with_spatial(position.is_some())
+                .with_volume(Volume::new(config.audio.sfx_volume)),
+        ));
+        if let Some(position) = position {
+            // Not parented to whatever made the sound, as it might not be around for long.
+            voice.insert(Transform::from_translation(position.extend(0.)));
+        }
+    }
+}
@@ -0,0 +1,289 @@
+//! Checks every level in `assets/levels` for problems, so that designers get a regression check
+//! whenever the physics changes.
+//!
+//! Each level definition is first checked for consistency. Then its reference replay, kept
+//! alongside it as a `.replay.ron`, is played back in a headless copy of the game, as fast as the
+//! machine allows. The level passes if the replay still rescues enough Yups. Record a reference
+//! replay by rescuing enough Yups in a dev build. A level without one is skipped, rather than
+//! failed, until it has one.
+//!
+//! Run with `cargo run --bin verify_levels`. Exits with a failure code if any level fails. There's
+//! no window, but collisions are still worked out on the GPU, so a graphics adapter is needed.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use bevy::{
+    asset::RenderAssetUsages,
+    image::{CompressedImageFormats, ImageSampler, ImageType},
+    prelude::*,
+};
+use home::{
+    GamePlugin,
+    assets::Levels,
+    dialogue::{Dialogue, skip_dialogue},
+    game::{
+        Game,
+        level::definition::LevelDefinition,
+        replay::{Playback, Recording, Replay, replay_path},
+        stats::LevelStats,
+    },
+    screens::Screen,
+};
+use tiny_bail::prelude::*;
+
+const ASSETS_DIR: &str = "assets";
+const LEVELS_DIR: &str = "assets/levels";
+const LEVEL_EXTENSION: &str = ".level.ron";
+// How long a level can carry on past the end of its replay before giving up on it, in game
+// seconds. Yups can still be making their way home well after the last input.
+const OVERTIME_SECS: f32 = 120.;
+
+fn main() -> ExitCode {
+    let entries = match fs::read_dir(LEVELS_DIR) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("could not read {LEVELS_DIR}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut paths: Vec<_> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.to_string_lossy().ends_with(LEVEL_EXTENSION))
+        .collect();
+    paths.sort();
+
+    let mut failures = 0;
+    let mut runs = vec![];
+    for path in &paths {
+        match verify(path) {
+            Ok(Some(run)) => runs.push(run),
+            Ok(None) => println!("SKIP {}: no reference replay", path.display()),
+            Err(problems) => {
+                failures += 1;
+                println!("FAIL {}", path.display());
+                for problem in problems {
+                    println!("    {problem}");
+                }
+            }
+        }
+    }
+
+    if !runs.is_empty() {
+        failures += play_back(runs);
+    }
+
+    println!("{} levels checked, {failures} failed", paths.len());
+    if failures > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// A level that passed its static checks, ready to be played back.
+struct LevelRun {
+    /// Relative to the assets directory, for loading through the asset server.
+    asset_path: String,
+    path: PathBuf,
+    replay: Replay,
+}
+
+/// Checks a level, and reads its reference replay if it has one.
+fn verify(path: &Path) -> Result<Option<LevelRun>, Vec<String>> {
+    let contents = fs::read_to_string(path).map_err(|e| vec![e.to_string()])?;
+    let definition: LevelDefinition = ron::from_str(&contents).map_err(|e| vec![e.to_string()])?;
+    let terrain_size = terrain_size(&definition.terrain).map_err(|e| vec![e])?;
+
+    let problems: Vec<String> = definition
+        .validate(terrain_size)
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    if !problems.is_empty() {
+        return Err(problems);
+    }
+
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let asset_path = format!("levels/{file_name}");
+    let replay_file = Path::new(ASSETS_DIR).join(replay_path(&asset_path));
+    let replay = match fs::read_to_string(&replay_file) {
+        Ok(replay) => replay,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(vec![format!(
+                "reference replay {}: {e}",
+                replay_file.display()
+            )]);
+        }
+    };
+    let replay = ron::from_str(&replay)
+        .map_err(|e| vec![format!("reference replay {}: {e}", replay_file.display())])?;
+
+    Ok(Some(LevelRun {
+        asset_path,
+        path: path.to_path_buf(),
+        replay,
+    }))
+}
+
+fn terrain_size(terrain: &str) -> Result<UVec2, String> {
+    let path = Path::new(ASSETS_DIR).join(terrain);
+    let bytes = fs::read(&path).map_err(|e| format!("terrain {}: {e}", path.display()))?;
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    let image = Image::from_buffer(
+        &bytes,
+        ImageType::Extension(extension),
+        CompressedImageFormats::NONE,
+        true,
+        ImageSampler::Default,
+        RenderAssetUsages::MAIN_WORLD,
+    )
+    .map_err(|e| format!("terrain {}: {e}", path.display()))?;
+    Ok(image.size())
+}
+
+/// Plays each level through with its replay, one after the other, and returns how many failed.
+fn play_back(runs: Vec<LevelRun>) -> u32 {
+    let mut app = App::new();
+    app.add_plugins(GamePlugin { headless: true });
+    app.insert_resource(Verification {
+        current: 0,
+        failures: 0,
+        level: None,
+        runs,
+    });
+    app.add_systems(Startup, skip_splash);
+    app.add_systems(
+        Update,
+        (
+            start_next_level.run_if(in_state(Screen::Title)),
+            start_level.run_if(in_state(Screen::Intro)),
+            skip_dialogue.run_if(resource_exists::<Dialogue>),
+            finish_level.run_if(in_state(Screen::InGame)),
+        ),
+    );
+
+    match app.run() {
+        AppExit::Success => 0,
+        AppExit::Error(failures) => failures.get().into(),
+    }
+}
+
+#[derive(Resource)]
+struct Verification {
+    // Which of the runs is being played.
+    current: usize,
+    failures: u8,
+    // The level being played, once it has started loading.
+    level: Option<Handle<LevelDefinition>>,
+    runs: Vec<LevelRun>,
+}
+
+fn skip_splash(mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Loading);
+}
+
+// The title screen is where each level starts from, and where the game goes back to afterwards.
+fn start_next_level(
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+    mut exit: EventWriter<AppExit>,
+    mut levels: ResMut<Levels>,
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut verification: ResMut<Verification>,
+) {
+    let Some(run) = verification.runs.get(verification.current) else {
+        exit.send(match verification.failures {
+            0 => AppExit::Success,
+            failures => AppExit::from_code(failures),
+        });
+        return;
+    };
+    let asset_path = run.asset_path.clone();
+    let replay = run.replay.clone();
+    let level = verification
+        .level
+        .get_or_insert_with(|| asset_server.load(asset_path))
+        .clone();
+
+    if asset_server.load_state(&level).is_failed()
+        || asset_server
+            .recursive_dependency_load_state(&level)
+            .is_failed()
+    {
+        println!(
+            "FAIL {}",
+            verification.runs[verification.current].path.display()
+        );
+        println!("    could not be loaded by the game");
+        verification.failures = verification.failures.saturating_add(1);
+        next_run(&mut verification);
+        return;
+    }
+    if !asset_server.is_loaded_with_dependencies(&level) {
+        return;
+    }
+
+    // Everything in the game plays whichever level this points at.
+    levels.first = level;
+    commands.insert_resource(Playback::new(replay));
+    next_screen.set(Screen::Intro);
+}
+
+fn start_level(mut next_game: ResMut<NextState<Game>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::InGame);
+    next_game.set(Game::Playing);
+}
+
+// The level is over once every Yup is accounted for, or time runs out, or the replay has long
+// since finished without that happening.
+fn finish_level(
+    game: Option<Res<State<Game>>>,
+    mut next_screen: ResMut<NextState<Screen>>,
+    playback: Option<Res<Playback>>,
+    recording: Res<Recording>,
+    stats: Res<LevelStats>,
+    mut verification: ResMut<Verification>,
+) {
+    let playback = rq!(playback);
+    let out_of_time = game.is_some_and(|game| *game.get() == Game::Failed);
+    let overtime = recording.elapsed_secs() > playback.replay.duration_secs + OVERTIME_SECS;
+    if !(stats.all_accounted_for() || out_of_time || overtime) {
+        return;
+    }
+
+    let passed = stats.rescued >= stats.rescue_target;
+    println!(
+        "{} {}: rescued {} of {}, needed {}",
+        if passed { "PASS" } else { "FAIL" },
+        verification.runs[verification.current].path.display(),
+        stats.rescued,
+        stats.total,
+        stats.rescue_target,
+    );
+    if out_of_time {
+        println!("    ran out of time");
+    } else if overtime {
+        println!("    gave up with {} Yups still out", stats.out());
+    }
+    if !passed {
+        verification.failures = verification.failures.saturating_add(1);
+    }
+
+    next_run(&mut verification);
+    next_screen.set(Screen::Title);
+}
+
+fn next_run(verification: &mut Verification) {
+    verification.current += 1;
+    verification.level = None;
+}
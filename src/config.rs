@@ -0,0 +1,550 @@
+use bevy::{
+    audio::Volume,
+    prelude::*,
+    window::{MonitorSelection, PresentMode, WindowMode},
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{game::camera::CameraZoom, viewport::ViewportScaling};
+
+#[cfg(not(target_arch = "wasm32"))]
+const CONFIG_PATH: &str = "config.ron";
+// How long configuration errors stay on screen.
+const ERRORS_DISPLAY_SECS: f32 = 10.;
+// Any shorter and the splash screen's fade is too quick to see.
+const SPLASH_FADE_SECS_MIN: f32 = 0.05;
+#[cfg(target_arch = "wasm32")]
+const STORAGE_KEY: &str = "all-the-way-home.config";
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(Startup, show_config_errors);
+    app.add_systems(Update, dismiss_config_errors);
+    app.add_systems(PostUpdate, apply_config.run_if(resource_changed::<Config>));
+}
+
+/// Player-adjustable settings, loaded from `config.ron` on native builds and from local storage on
+/// the web. Anything missing from the stored config falls back to its default.
+#[derive(Resource, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub audio: AudioConfig,
+    pub keys: KeyBindings,
+    pub timers: TimerConfig,
+    pub window: WindowConfig,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioConfig {
+    /// Between 0 and 1.
+    pub master_volume: f32,
+    /// Between 0 and 1, as a fraction of the master volume.
+    pub music_volume: f32,
+    /// Between 0 and 1, as a fraction of the master volume.
+    pub sfx_volume: f32,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            master_volume: 0.3,
+            music_volume: 1.,
+            sfx_volume: 1.,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub advance_dialogue: KeyCode,
+    pub fast_forward: KeyCode,
+    pub next_skill: KeyCode,
+    pub pan_down: KeyCode,
+    pub pan_left: KeyCode,
+    pub pan_right: KeyCode,
+    pub pan_up: KeyCode,
+    pub pause: KeyCode,
+    pub previous_skill: KeyCode,
+    pub release_faster: KeyCode,
+    pub release_slower: KeyCode,
+    pub skip_dialogue: KeyCode,
+    pub skip_splash: KeyCode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            advance_dialogue: KeyCode::Space,
+            fast_forward: KeyCode::KeyF,
+            next_skill: KeyCode::KeyE,
+            pan_down: KeyCode::ArrowDown,
+            pan_left: KeyCode::ArrowLeft,
+            pan_right: KeyCode::ArrowRight,
+            pan_up: KeyCode::ArrowUp,
+            pause: KeyCode::Escape,
+            previous_skill: KeyCode::KeyQ,
+            release_faster: KeyCode::Equal,
+            release_slower: KeyCode::Minus,
+            skip_dialogue: KeyCode::Escape,
+            skip_splash: KeyCode::Escape,
+        }
+    }
+}
+
+/// Everything that can be bound to a key, in the order they're listed in the options menu.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum KeyAction {
+    Pause,
+    PanLeft,
+    PanRight,
+    PanUp,
+    PanDown,
+    PreviousSkill,
+    NextSkill,
+    ReleaseSlower,
+    ReleaseFaster,
+    FastForward,
+    AdvanceDialogue,
+    SkipDialogue,
+    SkipSplash,
+}
+
+/// When a key action can happen. Actions from different contexts can share a key, since they're
+/// never wanted at the same time.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum KeyContext {
+    /// The game is held up while dialogue is shown.
+    Dialogue,
+    Game,
+    Splash,
+}
+
+impl KeyAction {
+    pub const ALL: [Self; 13] = [
+        Self::Pause,
+        Self::PanLeft,
+        Self::PanRight,
+        Self::PanUp,
+        Self::PanDown,
+        Self::PreviousSkill,
+        Self::NextSkill,
+        Self::ReleaseSlower,
+        Self::ReleaseFaster,
+        Self::FastForward,
+        Self::AdvanceDialogue,
+        Self::SkipDialogue,
+        Self::SkipSplash,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Pause => "Pause",
+            Self::PanLeft => "Pan left",
+            Self::PanRight => "Pan right",
+            Self::PanUp => "Pan up",
+            Self::PanDown => "Pan down",
+            Self::PreviousSkill => "Previous skill",
+            Self::NextSkill => "Next skill",
+            Self::ReleaseSlower => "Release slower",
+            Self::ReleaseFaster => "Release faster",
+            Self::FastForward => "Fast forward",
+            Self::AdvanceDialogue => "Advance dialogue",
+            Self::SkipDialogue => "Skip dialogue",
+            Self::SkipSplash => "Skip splash",
+        }
+    }
+
+    fn context(&self) -> KeyContext {
+        match self {
+            Self::AdvanceDialogue | Self::SkipDialogue => KeyContext::Dialogue,
+            Self::SkipSplash => KeyContext::Splash,
+            Self::Pause
+            | Self::PanLeft
+            | Self::PanRight
+            | Self::PanUp
+            | Self::PanDown
+            | Self::PreviousSkill
+            | Self::NextSkill
+            | Self::ReleaseSlower
+            | Self::ReleaseFaster
+            | Self::FastForward => KeyContext::Game,
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn get(&self, action: KeyAction) -> KeyCode {
+        match action {
+            KeyAction::Pause => self.pause,
+            KeyAction::PanLeft => self.pan_left,
+            KeyAction::PanRight => self.pan_right,
+            KeyAction::PanUp => self.pan_up,
+            KeyAction::PanDown => self.pan_down,
+            KeyAction::PreviousSkill => self.previous_skill,
+            KeyAction::NextSkill => self.next_skill,
+            KeyAction::ReleaseSlower => self.release_slower,
+            KeyAction::ReleaseFaster => self.release_faster,
+            KeyAction::FastForward => self.fast_forward,
+            KeyAction::AdvanceDialogue => self.advance_dialogue,
+            KeyAction::SkipDialogue => self.skip_dialogue,
+            KeyAction::SkipSplash => self.skip_splash,
+        }
+    }
+
+    fn get_mut(&mut self, action: KeyAction) -> &mut KeyCode {
+        match action {
+            KeyAction::Pause => &mut self.pause,
+            KeyAction::PanLeft => &mut self.pan_left,
+            KeyAction::PanRight => &mut self.pan_right,
+            KeyAction::PanUp => &mut self.pan_up,
+            KeyAction::PanDown => &mut self.pan_down,
+            KeyAction::PreviousSkill => &mut self.previous_skill,
+            KeyAction::NextSkill => &mut self.next_skill,
+            KeyAction::ReleaseSlower => &mut self.release_slower,
+            KeyAction::ReleaseFaster => &mut self.release_faster,
+            KeyAction::FastForward => &mut self.fast_forward,
+            KeyAction::AdvanceDialogue => &mut self.advance_dialogue,
+            KeyAction::SkipDialogue => &mut self.skip_dialogue,
+            KeyAction::SkipSplash => &mut self.skip_splash,
+        }
+    }
+
+    /// Binds a key to an action. If another action already uses that key, it takes over the old
+    /// key instead, so bindings never clash.
+    pub fn rebind(&mut self, action: KeyAction, key: KeyCode) {
+        let old = self.get(action);
+        let clashing = KeyAction::ALL
+            .into_iter()
+            .find(|a| *a != action && a.context() == action.context() && self.get(*a) == key);
+        if let Some(clashing) = clashing {
+            *self.get_mut(clashing) = old;
+        }
+        *self.get_mut(action) = key;
+    }
+
+    /// The first key bound to more than one action that can happen at the same time, if any. See
+    /// [`KeyContext`].
+    fn duplicate(&self) -> Option<KeyCode> {
+        KeyAction::ALL
+            .iter()
+            .enumerate()
+            .find(|(i, a)| {
+                KeyAction::ALL[i + 1..]
+                    .iter()
+                    .any(|b| a.context() == b.context() && self.get(**a) == self.get(*b))
+            })
+            .map(|(_, a)| self.get(*a))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimerConfig {
+    pub splash_fade_secs: f32,
+    pub splash_secs: f32,
+}
+
+impl Default for TimerConfig {
+    fn default() -> Self {
+        Self {
+            splash_fade_secs: 0.6,
+            splash_secs: 1.8,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowConfig {
+    pub fullscreen: bool,
+    /// Initial window size, in logical pixels. The window can be resized freely after that.
+    pub height: f32,
+    /// Restricts camera zoom to scales that keep terrain pixels square and whole.
+    pub pixel_perfect: bool,
+    pub scaling: ViewportScaling,
+    pub vsync: bool,
+    pub width: f32,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            fullscreen: false,
+            height: 720.,
+            pixel_perfect: false,
+            scaling: ViewportScaling::Fit,
+            vsync: true,
+            width: 1280.,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("{0:?} is bound to more than one action, using the default key bindings")]
+    DuplicateBinding(KeyCode),
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("could not access {CONFIG_PATH}: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("{field} must be between {min} and {max}, using the default of {default}")]
+    OutOfRange {
+        field: &'static str,
+        min: f32,
+        max: f32,
+        default: f32,
+    },
+    #[error("could not read the config, using defaults: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    #[error("could not write the config: {0}")]
+    Serialize(#[from] ron::Error),
+    #[cfg(target_arch = "wasm32")]
+    #[error("local storage is unavailable, settings won't be saved")]
+    Storage,
+}
+
+/// Anything that went wrong loading the config, for display once the window is up.
+#[derive(Resource, Debug, Default, Deref)]
+pub struct ConfigErrors(pub Vec<String>);
+
+impl Config {
+    /// Loads the stored config, falling back to defaults for anything missing or invalid. Any
+    /// problems are returned alongside, rather than failing outright: a broken config file
+    /// shouldn't stop anyone playing.
+    pub fn load() -> (Self, Vec<ConfigError>) {
+        let mut errors = vec![];
+        let mut config = match Self::read() {
+            Ok(Some(stored)) => match ron::from_str(&stored) {
+                Ok(config) => config,
+                Err(e) => {
+                    errors.push(ConfigError::from(e));
+                    Self::default()
+                }
+            },
+            Ok(None) => Self::default(),
+            Err(e) => {
+                errors.push(e);
+                Self::default()
+            }
+        };
+        errors.extend(config.validate());
+        (config, errors)
+    }
+
+    pub fn save(&self) -> Result<(), ConfigError> {
+        let serialized = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        Self::write(&serialized)
+    }
+
+    /// Resets any invalid values to their defaults, returning what was wrong.
+    pub fn validate(&mut self) -> Vec<ConfigError> {
+        let mut errors = vec![];
+        let defaults = Self::default();
+
+        let mut check_range = |field, value: &mut f32, min, max, default| {
+            if !(min..=max).contains(value) {
+                errors.push(ConfigError::OutOfRange {
+                    field,
+                    min,
+                    max,
+                    default,
+                });
+                *value = default;
+            }
+        };
+        check_range(
+            "audio.master_volume",
+            &mut self.audio.master_volume,
+            0.,
+            1.,
+            defaults.audio.master_volume,
+        );
+        check_range(
+            "audio.music_volume",
+            &mut self.audio.music_volume,
+            0.,
+            1.,
+            defaults.audio.music_volume,
+        );
+        check_range(
+            "audio.sfx_volume",
+            &mut self.audio.sfx_volume,
+            0.,
+            1.,
+            defaults.audio.sfx_volume,
+        );
+        // Long enough for the shortest fade in and out.
+        check_range(
+            "timers.splash_secs",
+            &mut self.timers.splash_secs,
+            SPLASH_FADE_SECS_MIN * 2.,
+            60.,
+            defaults.timers.splash_secs,
+        );
+        // The fade in and out both need to fit within the splash itself.
+        check_range(
+            "timers.splash_fade_secs",
+            &mut self.timers.splash_fade_secs,
+            SPLASH_FADE_SECS_MIN,
+            self.timers.splash_secs / 2.,
+            defaults
+                .timers
+                .splash_fade_secs
+                .min(self.timers.splash_secs / 2.),
+        );
+        check_range(
+            "window.width",
+            &mut self.window.width,
+            320.,
+            7680.,
+            defaults.window.width,
+        );
+        check_range(
+            "window.height",
+            &mut self.window.height,
+            180.,
+            4320.,
+            defaults.window.height,
+        );
+
+        if let Some(key) = self.keys.duplicate() {
+            errors.push(ConfigError::DuplicateBinding(key));
+            self.keys = defaults.keys;
+        }
+
+        errors
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn read() -> Result<Option<String>, ConfigError> {
+        match std::fs::read_to_string(CONFIG_PATH) {
+            Ok(stored) => Ok(Some(stored)),
+            // First run, most likely.
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn write(serialized: &str) -> Result<(), ConfigError> {
+        Ok(std::fs::write(CONFIG_PATH, serialized)?)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn read() -> Result<Option<String>, ConfigError> {
+        local_storage()?
+            .get_item(STORAGE_KEY)
+            .map_err(|_| ConfigError::Storage)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn write(serialized: &str) -> Result<(), ConfigError> {
+        local_storage()?
+            .set_item(STORAGE_KEY, serialized)
+            .map_err(|_| ConfigError::Storage)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Result<web_sys::Storage, ConfigError> {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .ok_or(ConfigError::Storage)
+}
+
+impl WindowConfig {
+    pub fn mode(&self) -> WindowMode {
+        if self.fullscreen {
+            WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+        } else {
+            WindowMode::Windowed
+        }
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        if self.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        }
+    }
+}
+
+#[derive(Component)]
+struct ConfigErrorsDisplay(Timer);
+
+fn show_config_errors(mut commands: Commands, errors: Res<ConfigErrors>) {
+    if errors.is_empty() {
+        return;
+    }
+    // Logging isn't set up yet when the config is loaded, so report the errors here instead.
+    for error in errors.iter() {
+        warn!("{error}");
+    }
+
+    commands
+        .spawn((
+            Name::new("Config Errors"),
+            ConfigErrorsDisplay(Timer::from_seconds(ERRORS_DISPLAY_SECS, TimerMode::Once)),
+            BackgroundColor(Color::srgba(0.3, 0.05, 0.05, 0.9)),
+            GlobalZIndex(i32::MAX),
+            Node {
+                flex_direction: FlexDirection::Column,
+                left: Val::Px(10.),
+                padding: UiRect::all(Val::Px(8.)),
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.),
+                ..default()
+            },
+        ))
+        .with_children(|p| {
+            for error in errors.iter() {
+                p.spawn((Text::new(error.clone()), TextFont {
+                    font_size: 14.,
+                    ..default()
+                }));
+            }
+        });
+}
+
+fn dismiss_config_errors(
+    mut commands: Commands,
+    mut displays: Query<(Entity, &mut ConfigErrorsDisplay)>,
+    time: Res<Time>,
+) {
+    for (entity, mut display) in &mut displays {
+        if display.0.tick(time.delta()).just_finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn apply_config(
+    config: Res<Config>,
+    mut global_volume: ResMut<GlobalVolume>,
+    mut previous: Local<Option<Config>>,
+    mut scaling: ResMut<ViewportScaling>,
+    mut window: Single<&mut Window>,
+    mut zoom: ResMut<CameraZoom>,
+) {
+    global_volume.volume = Volume::new(config.audio.master_volume);
+    scaling.set_if_neq(config.window.scaling);
+    zoom.pixel_perfect = config.window.pixel_perfect;
+
+    window.mode = config.window.mode();
+    window.present_mode = config.window.present_mode();
+    // Only resize the window when the configured size actually changes, otherwise we'd undo any
+    // resizing the player has done by hand.
+    let size_changed = previous.as_ref().is_none_or(|previous| {
+        previous.window.width != config.window.width
+            || previous.window.height != config.window.height
+    });
+    if size_changed {
+        window
+            .resolution
+            .set(config.window.width, config.window.height);
+    }
+
+    *previous = Some(config.clone());
+}
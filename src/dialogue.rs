@@ -0,0 +1,244 @@
+use bevy::{prelude::*, ui::Val::*};
+use leafwing_input_manager::common_conditions::action_just_pressed;
+use serde::{Deserialize, Serialize};
+use tiny_bail::prelude::*;
+
+use crate::{game::Game, input::GameAction, screens::Screen};
+
+const BACKGROUND_COLOR: Color = Color::srgba(0.05, 0.05, 0.1, 0.9);
+// How quickly lines are typed out.
+const CHARS_PER_SEC: f32 = 40.;
+const PORTRAIT_SIZE: f32 = 64.;
+const SPEAKER_COLOR: Color = Color::srgb(0.95, 0.8, 0.2);
+
+pub fn plugin(app: &mut App) {
+    app.add_event::<StartDialogue>();
+    app.add_systems(OnExit(Screen::InGame), remove_dialogue);
+    app.add_systems(OnExit(Screen::Intro), remove_dialogue);
+    app.add_systems(
+        Update,
+        (
+            start_dialogue,
+            skip_dialogue.run_if(
+                resource_exists::<Dialogue>.and(action_just_pressed(GameAction::SkipDialogue)),
+            ),
+            advance_dialogue.run_if(
+                resource_exists::<Dialogue>.and(action_just_pressed(GameAction::AdvanceDialogue)),
+            ),
+            type_dialogue.run_if(resource_exists::<Dialogue>),
+            show_dialogue.run_if(resource_exists_and_changed::<Dialogue>),
+        )
+            .chain(),
+    );
+}
+
+/// Something said by one of the characters.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct DialogueLine {
+    pub speaker: String,
+    /// Asset path of a picture of the speaker, shown alongside what they say.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub portrait: Option<String>,
+    pub text: String,
+}
+
+/// Send this to show some dialogue, replacing any that's already showing. While it's showing
+/// during a level, the level waits.
+#[derive(Event, Debug)]
+pub struct StartDialogue(pub Vec<DialogueLine>);
+
+/// The dialogue being shown. Only present while there is some.
+#[derive(Resource, Debug)]
+pub struct Dialogue {
+    /// Which of the lines is showing.
+    current: usize,
+    lines: Vec<DialogueLine>,
+    /// How many characters of the current line have been typed out so far. Fractional, so that
+    /// typing goes at the same speed whatever the frame rate.
+    typed: f32,
+}
+
+impl Dialogue {
+    fn line(&self) -> Option<&DialogueLine> {
+        self.lines.get(self.current)
+    }
+
+    fn fully_typed(&self) -> bool {
+        self.line()
+            .is_none_or(|line| self.typed as usize >= line.text.chars().count())
+    }
+}
+
+/// The box the dialogue is shown in, which goes once the dialogue is over.
+#[derive(Component, Debug)]
+pub struct DialogueBox;
+
+#[derive(Component, Debug)]
+struct DialoguePortrait;
+
+#[derive(Component, Debug)]
+struct DialogueSpeaker;
+
+#[derive(Component, Debug)]
+struct DialogueText;
+
+pub fn start_dialogue(
+    mut commands: Commands,
+    boxes: Query<Entity, With<DialogueBox>>,
+    mut events: EventReader<StartDialogue>,
+    game: Option<Res<State<Game>>>,
+    mut next_game: ResMut<NextState<Game>>,
+    screen: Res<State<Screen>>,
+) {
+    // Only the latest dialogue is shown, if several start at once.
+    let StartDialogue(lines) = rq!(events.read().last());
+    if lines.is_empty() {
+        return;
+    }
+
+    for entity in &boxes {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.insert_resource(Dialogue {
+        current: 0,
+        lines: lines.clone(),
+        typed: 0.,
+    });
+    spawn_dialogue_box(&mut commands, screen.get().clone());
+
+    if game.is_some_and(|game| *game.get() == Game::Playing) {
+        next_game.set(Game::Dialogue);
+    }
+}
+
+fn spawn_dialogue_box(commands: &mut Commands, screen: Screen) {
+    commands
+        .spawn((
+            Name::new("Dialogue Box"),
+            DialogueBox,
+            BackgroundColor(BACKGROUND_COLOR),
+            Node {
+                align_items: AlignItems::FlexStart,
+                bottom: Percent(5.),
+                column_gap: Px(12.),
+                left: Percent(10.),
+                padding: UiRect::all(Px(12.)),
+                position_type: PositionType::Absolute,
+                width: Percent(80.),
+                ..default()
+            },
+            StateScoped(screen),
+        ))
+        .with_children(|p| {
+            p.spawn((
+                Name::new("Portrait"),
+                DialoguePortrait,
+                ImageNode::default(),
+                Node {
+                    // Only shown for lines with a portrait.
+                    display: Display::None,
+                    height: Px(PORTRAIT_SIZE),
+                    width: Px(PORTRAIT_SIZE),
+                    ..default()
+                },
+            ));
+            p.spawn(Node {
+                flex_direction: FlexDirection::Column,
+                flex_grow: 1.,
+                row_gap: Px(6.),
+                ..default()
+            })
+            .with_children(|p| {
+                p.spawn((
+                    Name::new("Speaker"),
+                    DialogueSpeaker,
+                    Text::default(),
+                    TextColor(SPEAKER_COLOR),
+                ));
+                p.spawn((Name::new("Dialogue Text"), DialogueText, Text::default()));
+            });
+        });
+}
+
+/// Ends the dialogue at once, however far through it is.
+pub fn skip_dialogue(
+    mut commands: Commands,
+    boxes: Query<Entity, With<DialogueBox>>,
+    game: Option<Res<State<Game>>>,
+    mut next_game: ResMut<NextState<Game>>,
+) {
+    end_dialogue(&mut commands, &boxes, game.as_deref(), &mut next_game);
+}
+
+// Finishes typing the current line if it's still going, and otherwise moves on to the next.
+fn advance_dialogue(
+    mut commands: Commands,
+    boxes: Query<Entity, With<DialogueBox>>,
+    mut dialogue: ResMut<Dialogue>,
+    game: Option<Res<State<Game>>>,
+    mut next_game: ResMut<NextState<Game>>,
+) {
+    if !dialogue.fully_typed() {
+        dialogue.typed = f32::MAX;
+        return;
+    }
+
+    dialogue.current += 1;
+    dialogue.typed = 0.;
+    if dialogue.line().is_none() {
+        end_dialogue(&mut commands, &boxes, game.as_deref(), &mut next_game);
+    }
+}
+
+fn end_dialogue(
+    commands: &mut Commands,
+    boxes: &Query<Entity, With<DialogueBox>>,
+    game: Option<&State<Game>>,
+    next_game: &mut NextState<Game>,
+) {
+    for entity in boxes {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<Dialogue>();
+    if game.is_some_and(|game| *game.get() == Game::Dialogue) {
+        next_game.set(Game::Playing);
+    }
+}
+
+// Leaving the screen takes the dialogue box with it, as it's scoped to the screen.
+fn remove_dialogue(mut commands: Commands) {
+    commands.remove_resource::<Dialogue>();
+}
+
+fn type_dialogue(mut dialogue: ResMut<Dialogue>, time: Res<Time<Real>>) {
+    // Typing goes at the same speed when the game is fast forwarded.
+    if !dialogue.fully_typed() {
+        dialogue.typed += CHARS_PER_SEC * time.delta_secs();
+    }
+}
+
+fn show_dialogue(
+    asset_server: Res<AssetServer>,
+    dialogue: Res<Dialogue>,
+    mut portraits: Query<(&mut ImageNode, &mut Node), With<DialoguePortrait>>,
+    mut speakers: Query<&mut Text, (With<DialogueSpeaker>, Without<DialogueText>)>,
+    mut texts: Query<&mut Text, (With<DialogueText>, Without<DialogueSpeaker>)>,
+) {
+    let line = rq!(dialogue.line());
+
+    for (mut image, mut node) in &mut portraits {
+        match &line.portrait {
+            Some(path) => {
+                image.image = asset_server.load(path);
+                node.display = Display::Flex;
+            }
+            None => node.display = Display::None,
+        }
+    }
+    for mut speaker in &mut speakers {
+        speaker.0.clone_from(&line.speaker);
+    }
+    for mut text in &mut texts {
+        text.0 = line.text.chars().take(dialogue.typed as usize).collect();
+    }
+}
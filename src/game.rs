@@ -1,6 +1,25 @@
+pub mod animation;
+pub mod background;
+pub mod camera;
+pub mod cursor;
+pub mod exits;
+pub mod hatch;
+pub mod hazards;
 pub mod level;
+pub mod lighting;
+pub mod minimap;
 pub mod movement;
+pub mod objects;
+pub mod particles;
 pub mod rendering;
+pub mod replay;
+pub mod skills;
+pub mod speed;
+pub mod stats;
+pub mod steel;
+pub mod timer;
+pub mod touch;
+pub mod triggers;
 pub mod yup;
 
 use bevy::prelude::*;
@@ -12,6 +31,8 @@ use crate::screens::Screen;
 pub enum Game {
     /// Player has successfully completed the level.
     Complete,
+    /// A dialogue is showing, and the level waits for it to finish.
+    Dialogue,
     /// Player has failed the level, menu shows with offer to retry or quit.
     Failed,
     /// Player has hit the pause key, pause menu shows.
@@ -27,5 +48,31 @@ pub enum Game {
 pub fn plugin(app: &mut App) {
     app.init_state::<Game>();
     app.enable_state_scoped_entities::<Game>();
-    app.add_plugins((level::plugin, movement::plugin, yup::plugin));
+    app.add_plugins((
+        animation::plugin,
+        background::plugin,
+        camera::plugin,
+        cursor::plugin,
+        exits::plugin,
+        hatch::plugin,
+        hazards::plugin,
+        level::plugin,
+        lighting::plugin,
+        minimap::plugin,
+        movement::plugin,
+    ));
+    // Split in two, as there are more than `add_plugins` takes in one go.
+    app.add_plugins((
+        objects::plugin,
+        particles::plugin,
+        replay::plugin,
+        skills::plugin,
+        speed::plugin,
+        stats::plugin,
+        steel::plugin,
+        timer::plugin,
+        touch::plugin,
+        triggers::plugin,
+        yup::plugin,
+    ));
 }
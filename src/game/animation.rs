@@ -0,0 +1,86 @@
+pub mod sheet;
+
+use bevy::prelude::*;
+use tiny_bail::prelude::*;
+
+use crate::game::Game;
+use sheet::{AnimationSheet, AnimationSheetLoader};
+
+pub fn plugin(app: &mut App) {
+    app.init_asset::<AnimationSheet>();
+    app.init_asset_loader::<AnimationSheetLoader>();
+    // Frames advance in fixed time, along with everything else that moves, so animations keep in
+    // step with movement at any game speed.
+    app.add_systems(
+        FixedUpdate,
+        advance_animations.run_if(in_state(Game::Playing)),
+    );
+}
+
+/// Plays clips from an [`AnimationSheet`] on the entity's [`Sprite`].
+#[derive(Component, Debug)]
+#[require(Sprite)]
+pub struct SpriteAnimation {
+    /// The name of the clip playing, which must be in the sheet's `clips`.
+    clip: &'static str,
+    /// Index into the clip's frames, rather than the sheet.
+    frame: usize,
+    /// How long the current frame has been shown for.
+    frame_elapsed_secs: f32,
+    sheet: Handle<AnimationSheet>,
+}
+
+impl SpriteAnimation {
+    pub fn new(sheet: Handle<AnimationSheet>, clip: &'static str) -> Self {
+        Self {
+            clip,
+            frame: 0,
+            frame_elapsed_secs: 0.,
+            sheet,
+        }
+    }
+
+    /// Switches to another clip, from the start. Does nothing if the clip is already playing.
+    pub fn play(&mut self, clip: &'static str) {
+        if self.clip != clip {
+            *self = Self::new(self.sheet.clone(), clip);
+        }
+    }
+}
+
+pub fn advance_animations(
+    mut animations: Query<(&mut SpriteAnimation, &mut Sprite)>,
+    sheets: Res<Assets<AnimationSheet>>,
+    time: Res<Time>,
+) {
+    for (mut animation, mut sprite) in &mut animations {
+        let sheet = cq!(sheets.get(&animation.sheet));
+        let clip = cq!(sheet.clips.get(animation.clip));
+
+        animation.frame_elapsed_secs += time.delta_secs();
+        while animation.frame_elapsed_secs >= clip.frame_secs && clip.frame_secs > 0. {
+            animation.frame_elapsed_secs -= clip.frame_secs;
+            animation.frame += 1;
+        }
+        animation.frame = match clip.frames.len() {
+            0 => 0,
+            len if clip.looping => animation.frame % len,
+            len => animation.frame.min(len - 1),
+        };
+
+        let index = cq!(clip.frames.get(animation.frame));
+        // The sheet may have been hot reloaded with a different image or layout.
+        if sprite.image != sheet.image_handle {
+            sprite.image = sheet.image_handle.clone();
+        }
+        match &mut sprite.texture_atlas {
+            Some(atlas) if atlas.layout == sheet.layout => atlas.index = *index,
+            atlas => {
+                *atlas = Some(TextureAtlas {
+                    index: *index,
+                    layout: sheet.layout.clone(),
+                });
+            }
+        }
+    }
+}
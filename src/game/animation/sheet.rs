@@ -0,0 +1,86 @@
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+    utils::HashMap,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+/// A sprite sheet and the animations in it, as described by a `.anim.ron` file in
+/// `assets/animations`.
+///
+/// Frames are numbered left to right, top to bottom, starting from 0, so artists can rearrange the
+/// sheet and update the numbers here without touching any code.
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct AnimationSheet {
+    /// Asset path of the sprite sheet image.
+    pub image: String,
+    /// The size of each frame, in pixels.
+    pub frame_size: UVec2,
+    pub columns: u32,
+    pub rows: u32,
+    /// The animations, by name. See [`CharacterState::clip`](crate::game::yup::CharacterState::clip)
+    /// and [`HazardKind::clip`](crate::game::hazards::HazardKind::clip) for the names Yups and
+    /// hazards expect.
+    pub clips: HashMap<String, AnimationClip>,
+    /// The sprite sheet image itself, loaded as a dependency of the sheet.
+    #[serde(skip)]
+    #[dependency]
+    pub image_handle: Handle<Image>,
+    /// Where each frame is in the image, built from the frame size and counts above.
+    #[serde(skip)]
+    pub layout: Handle<TextureAtlasLayout>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AnimationClip {
+    /// Indices into the sheet, in the order they're played.
+    pub frames: Vec<usize>,
+    /// How long each frame is shown for.
+    pub frame_secs: f32,
+    /// Whether to start again from the first frame, or stay on the last.
+    #[serde(default = "default_looping")]
+    pub looping: bool,
+}
+
+fn default_looping() -> bool {
+    true
+}
+
+#[derive(Default)]
+pub struct AnimationSheetLoader;
+
+#[derive(Debug, Error)]
+pub enum AnimationSheetLoaderError {
+    #[error("could not read animation sheet: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse animation sheet: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+}
+
+impl AssetLoader for AnimationSheetLoader {
+    type Asset = AnimationSheet;
+    type Settings = ();
+    type Error = AnimationSheetLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let mut sheet: AnimationSheet = ron::de::from_bytes(&bytes)?;
+        sheet.image_handle = load_context.load(sheet.image.clone());
+        sheet.layout = load_context.add_labeled_asset(
+            "layout".to_string(),
+            TextureAtlasLayout::from_grid(sheet.frame_size, sheet.columns, sheet.rows, None, None),
+        );
+        Ok(sheet)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["anim.ron"]
+    }
+}
@@ -0,0 +1,88 @@
+use bevy::{prelude::*, render::view::RenderLayers, transform::TransformSystem};
+use tiny_bail::prelude::*;
+
+use crate::{
+    GameSet, MainCamera,
+    assets::Levels,
+    game::{level::definition::LevelDefinition, rendering::GameRenderLayers},
+    screens::Screen,
+};
+
+// Behind the terrain, which sits at 0. Each nearer layer is drawn a little in front of the last.
+const FURTHEST_Z: f32 = -10.;
+const LAYER_Z_STEP: f32 = 0.01;
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(
+        OnEnter(Screen::InGame),
+        spawn_backgrounds.in_set(GameSet::Init),
+    );
+    // After the camera has moved for the frame, wherever that happened.
+    app.add_systems(
+        PostUpdate,
+        scroll_backgrounds
+            .before(TransformSystem::TransformPropagate)
+            .run_if(in_state(Screen::InGame)),
+    );
+}
+
+#[derive(Component, Debug)]
+struct Background {
+    factor: f32,
+    /// From the middle of the level, in world units.
+    offset: Vec2,
+}
+
+fn spawn_backgrounds(
+    mut commands: Commands,
+    definitions: Res<Assets<LevelDefinition>>,
+    images: Res<Assets<Image>>,
+    levels: Res<Levels>,
+) {
+    let definition = r!(definitions.get(&levels.first));
+    let terrain = r!(images.get(&definition.terrain_image));
+    let layers = definition
+        .backgrounds
+        .iter()
+        .zip(&definition.background_images);
+
+    for (i, (layer, image_handle)) in layers.enumerate() {
+        let image = c!(images.get(image_handle));
+        commands.spawn((
+            Name::new(format!("Background {i}")),
+            Background {
+                factor: layer.factor,
+                // Terrain pixels have y pointing down.
+                offset: Vec2::new(layer.offset.x, -layer.offset.y),
+            },
+            Sprite {
+                // Wide enough to cover the view wherever the camera is, as the camera never leaves
+                // the level.
+                custom_size: Some(Vec2::new(terrain.width() as f32, image.height() as f32)),
+                image: image_handle.clone(),
+                image_mode: SpriteImageMode::Tiled {
+                    stretch_value: 1.,
+                    tile_x: true,
+                    tile_y: false,
+                },
+                ..default()
+            },
+            Transform::from_xyz(0., 0., FURTHEST_Z + i as f32 * LAYER_Z_STEP),
+            RenderLayers::layer(GameRenderLayers::Background.into()),
+            StateScoped(Screen::InGame),
+        ));
+    }
+}
+
+fn scroll_backgrounds(
+    camera: Single<&Transform, (With<MainCamera>, Without<Background>)>,
+    mut layers: Query<(&Background, &mut Transform)>,
+) {
+    let camera = camera.translation.truncate();
+    for (background, mut transform) in &mut layers {
+        // A layer that moved with the terrain would stay where it is in the world, while one that
+        // didn't move at all would follow the camera exactly.
+        let position = camera * (1. - background.factor) + background.offset;
+        transform.translation = position.extend(transform.translation.z);
+    }
+}
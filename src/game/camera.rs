@@ -0,0 +1,254 @@
+use bevy::{
+    input::mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll, MouseScrollUnit},
+    math::StableInterpolate,
+    prelude::*,
+};
+use leafwing_input_manager::prelude::*;
+use tiny_bail::prelude::*;
+
+use crate::{
+    MainCamera,
+    game::level::Level,
+    input::GameAction,
+    screens::Screen,
+    viewport::{VIRTUAL_RESOLUTION, window_to_viewport},
+};
+
+// How close to the edge of the window (in logical pixels) the cursor needs to be to scroll.
+const EDGE_SCROLL_MARGIN: f32 = 16.;
+// Higher values catch up with the target faster.
+const EASING_DECAY_RATE: f32 = 12.;
+// World units per second, at a projection scale of 1.
+const PAN_SPEED: f32 = 900.;
+// Touchpads report scrolling in pixels rather than lines.
+const PIXELS_PER_SCROLL_LINE: f32 = 20.;
+// Scales that keep every terrain pixel the same size on screen, from most zoomed in to most
+// zoomed out.
+const PIXEL_PERFECT_SCALES: [f32; 5] = [1. / 4., 1. / 3., 1. / 2., 1., 2.];
+pub const ZOOM_MIN: f32 = 0.25;
+// Each line scrolled multiplies or divides the scale by this much.
+const ZOOM_STEP: f32 = 1.25;
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<CameraTarget>();
+    app.init_resource::<CameraZoom>();
+    app.add_systems(OnEnter(Screen::InGame), reset_camera);
+    app.add_systems(OnEnter(Screen::Editor), reset_camera);
+    // Menus and other screens expect the camera to be sitting at the origin.
+    app.add_systems(OnExit(Screen::InGame), reset_camera);
+    app.add_systems(OnExit(Screen::Editor), reset_camera);
+    app.add_systems(
+        Update,
+        (
+            edge_scroll,
+            action_pan,
+            drag_pan,
+            zoom,
+            clamp_target,
+            ease_camera,
+        )
+            .chain()
+            .run_if(in_state(Screen::InGame).or(in_state(Screen::Editor))),
+    );
+}
+
+/// Where the main camera is heading. Panning moves the target, and the camera eases towards it.
+#[derive(Resource, Debug, Default, Deref, DerefMut)]
+pub struct CameraTarget(pub Vec2);
+
+/// The orthographic scale the main camera is heading towards. Smaller values zoom in.
+#[derive(Resource, Debug)]
+pub struct CameraZoom {
+    pub target: f32,
+    /// Restricts zooming to scales at which terrain pixels map exactly onto screen pixels, and
+    /// snaps the camera position to the terrain pixel grid.
+    pub pixel_perfect: bool,
+}
+
+impl Default for CameraZoom {
+    fn default() -> Self {
+        Self {
+            target: 1.,
+            pixel_perfect: false,
+        }
+    }
+}
+
+impl CameraZoom {
+    /// The pixel perfect scale closest to the given one.
+    pub fn nearest_pixel_perfect(scale: f32, max: f32) -> f32 {
+        PIXEL_PERFECT_SCALES
+            .iter()
+            .copied()
+            .filter(|s| *s <= max)
+            .min_by(|a, b| (a - scale).abs().total_cmp(&(b - scale).abs()))
+            .unwrap_or(1.)
+    }
+
+    /// Steps through [`PIXEL_PERFECT_SCALES`], starting from the scale nearest to the target.
+    fn step_pixel_perfect(&self, zoom_out: bool, max: f32) -> f32 {
+        let scales = PIXEL_PERFECT_SCALES.iter().copied().filter(|s| *s <= max);
+        let next = if zoom_out {
+            scales.filter(|s| *s > self.target).reduce(f32::min)
+        } else {
+            scales.filter(|s| *s < self.target).reduce(f32::max)
+        };
+        next.unwrap_or(self.target)
+    }
+}
+
+/// The largest scale the camera can zoom out to, at which point the level fills the view.
+pub fn max_scale(level_size: Vec2) -> f32 {
+    (level_size / VIRTUAL_RESOLUTION).min_element().max(1.)
+}
+
+fn reset_camera(
+    camera: Single<(&mut OrthographicProjection, &mut Transform), With<MainCamera>>,
+    mut target: ResMut<CameraTarget>,
+    mut zoom: ResMut<CameraZoom>,
+) {
+    let (mut projection, mut transform) = camera.into_inner();
+    projection.scale = 1.;
+    transform.translation = Vec2::ZERO.extend(transform.translation.z);
+    **target = Vec2::ZERO;
+    zoom.target = 1.;
+}
+
+fn edge_scroll(
+    camera: Single<&OrthographicProjection, With<MainCamera>>,
+    mut target: ResMut<CameraTarget>,
+    time: Res<Time>,
+    window: Single<&Window>,
+) {
+    if !window.focused {
+        return;
+    }
+    let cursor_pos = rq!(window.cursor_position());
+
+    let mut direction = Vec2::ZERO;
+    if cursor_pos.x < EDGE_SCROLL_MARGIN {
+        direction.x -= 1.;
+    }
+    if cursor_pos.x > window.width() - EDGE_SCROLL_MARGIN {
+        direction.x += 1.;
+    }
+    // Window coordinates have y pointing down, world coordinates have it pointing up.
+    if cursor_pos.y < EDGE_SCROLL_MARGIN {
+        direction.y += 1.;
+    }
+    if cursor_pos.y > window.height() - EDGE_SCROLL_MARGIN {
+        direction.y -= 1.;
+    }
+
+    **target += direction * PAN_SPEED * camera.scale * time.delta_secs();
+}
+
+fn action_pan(
+    action_state: Res<ActionState<GameAction>>,
+    camera: Single<&OrthographicProjection, With<MainCamera>>,
+    mut target: ResMut<CameraTarget>,
+    time: Res<Time>,
+) {
+    // Keys pressed together give a diagonal longer than a stick can, so cap it.
+    let direction = action_state
+        .axis_pair(&GameAction::Pan)
+        .clamp_length_max(1.);
+    **target += direction * PAN_SPEED * camera.scale * time.delta_secs();
+}
+
+fn drag_pan(
+    action_state: Res<ActionState<GameAction>>,
+    camera: Single<(&Camera, &OrthographicProjection, &mut Transform), With<MainCamera>>,
+    mouse_motion: Res<AccumulatedMouseMotion>,
+    mut target: ResMut<CameraTarget>,
+) {
+    if !action_state.pressed(&GameAction::DragPan) {
+        return;
+    }
+
+    // Dragging should feel like the level is stuck to the cursor, so skip the easing entirely.
+    let (cam, projection, mut transform) = camera.into_inner();
+    // The view is letterboxed into the window at whatever size fits, so a pixel of mouse movement
+    // isn't a pixel of the world.
+    let viewport_width = rq!(cam.logical_viewport_size()).x;
+    let world_per_pixel = projection.area.width() / viewport_width;
+    let delta = Vec2::new(-mouse_motion.delta.x, mouse_motion.delta.y) * world_per_pixel;
+    **target = transform.translation.truncate() + delta;
+    transform.translation = target.extend(transform.translation.z);
+}
+
+fn zoom(
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    level: Query<&Level>,
+    scroll: Res<AccumulatedMouseScroll>,
+    mut target: ResMut<CameraTarget>,
+    window: Single<&Window>,
+    mut zoom: ResMut<CameraZoom>,
+) {
+    let lines = match scroll.unit {
+        MouseScrollUnit::Line => scroll.delta.y,
+        MouseScrollUnit::Pixel => scroll.delta.y / PIXELS_PER_SCROLL_LINE,
+    };
+    if lines == 0. {
+        return;
+    }
+
+    let (cam, cam_transform) = *camera;
+    let level = rq!(level.get_single());
+    let max = max_scale(level.size);
+
+    // Scrolling up zooms in, which means a smaller scale.
+    let old = zoom.target;
+    zoom.target = if zoom.pixel_perfect {
+        zoom.step_pixel_perfect(lines < 0., max)
+    } else {
+        (old * ZOOM_STEP.powf(-lines)).clamp(ZOOM_MIN, max)
+    };
+
+    // Keep whatever is under the cursor in the same place on screen while zooming.
+    let cursor_world_pos = window.cursor_position().and_then(|pos| {
+        cam.viewport_to_world_2d(cam_transform, window_to_viewport(cam, pos))
+            .ok()
+    });
+    if let Some(cursor_world_pos) = cursor_world_pos {
+        **target = cursor_world_pos + (**target - cursor_world_pos) * zoom.target / old;
+    }
+}
+
+pub fn clamp_target(level: Query<&Level>, mut target: ResMut<CameraTarget>, zoom: Res<CameraZoom>) {
+    let level = rq!(level.get_single());
+    let view_size = VIRTUAL_RESOLUTION * zoom.target;
+
+    // If the level is smaller than the view in either dimension, keep it centred.
+    let max = ((level.size - view_size) / 2.).max(Vec2::ZERO);
+    let clamped = target.clamp(-max, max);
+    // Avoid triggering change detection when nothing has moved.
+    if clamped != **target {
+        **target = clamped;
+    }
+}
+
+fn ease_camera(
+    camera: Single<(&mut OrthographicProjection, &mut Transform), With<MainCamera>>,
+    target: Res<CameraTarget>,
+    time: Res<Time>,
+    zoom: Res<CameraZoom>,
+) {
+    let (mut projection, mut transform) = camera.into_inner();
+    let mut pos = transform.translation.truncate();
+    pos.smooth_nudge(&target, EASING_DECAY_RATE, time.delta_secs());
+
+    if zoom.pixel_perfect {
+        // Easing through the scales in between would defeat the point.
+        projection.scale = zoom.target;
+        // Snap to whole terrain pixels, or whole screen pixels when zoomed out.
+        let grid = projection.scale.max(1.);
+        pos = (pos / grid).round() * grid;
+    } else {
+        projection
+            .scale
+            .smooth_nudge(&zoom.target, EASING_DECAY_RATE, time.delta_secs());
+    }
+
+    transform.translation = pos.extend(transform.translation.z);
+}
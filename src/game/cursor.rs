@@ -0,0 +1,219 @@
+use bevy::{
+    input::mouse::AccumulatedMouseMotion, math::StableInterpolate, picking::focus::HoverMap,
+    prelude::*,
+};
+use leafwing_input_manager::prelude::*;
+use tiny_bail::prelude::*;
+
+use crate::{
+    GameSet, MainCamera, game::yup::Yup, input::GameAction, screens::Screen, ui::pointer_over_ui,
+    viewport::window_to_viewport,
+};
+
+// Logical pixels per second, per second the stick is held.
+const CURSOR_ACCELERATION: f32 = 1200.;
+const CURSOR_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.8);
+// Logical pixels per second.
+const CURSOR_MAX_SPEED: f32 = 1100.;
+const CURSOR_MIN_SPEED: f32 = 200.;
+const CURSOR_SIZE: f32 = 14.;
+// How far away (in logical pixels) a Yup can be for the resting cursor to snap to it.
+const SNAP_DISTANCE: f32 = 28.;
+// Higher values snap faster.
+const SNAP_DECAY_RATE: f32 = 18.;
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<GameCursor>();
+    app.add_systems(
+        OnEnter(Screen::InGame),
+        spawn_virtual_cursor.in_set(GameSet::Init),
+    );
+    app.add_systems(OnExit(Screen::InGame), reset_game_cursor);
+    app.add_systems(
+        Update,
+        (
+            read_select,
+            track_mouse,
+            move_virtual_cursor,
+            snap_to_yups,
+            update_world_position,
+            update_virtual_cursor,
+        )
+            .chain()
+            .in_set(GameSet::RecordInput),
+    );
+}
+
+/// Where the player is pointing, whether with the mouse or the virtual cursor. Anything acting on
+/// whatever is under the cursor should read this, rather than asking the window.
+#[derive(Resource, Debug, Default)]
+pub struct GameCursor {
+    /// Within the main camera's viewport, in logical pixels.
+    pub viewport_position: Option<Vec2>,
+    pub world_position: Option<Vec2>,
+    /// Whether the player is selecting whatever is under the cursor, like holding the mouse button.
+    /// Never set while the mouse is over the UI, so that clicks on it don't reach the level.
+    pub select_pressed: bool,
+    pub select_just_pressed: bool,
+    pub source: CursorSource,
+    // How long the stick has been pushed for, to accelerate the cursor.
+    held_secs: f32,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum CursorSource {
+    #[default]
+    Mouse,
+    /// The virtual cursor, moved with a gamepad stick.
+    Gamepad,
+    /// A single finger on a touch screen.
+    Touch,
+}
+
+#[derive(Component, Debug)]
+struct VirtualCursor;
+
+fn spawn_virtual_cursor(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Virtual Cursor"),
+        VirtualCursor,
+        BackgroundColor(CURSOR_COLOR),
+        BorderColor(Color::BLACK),
+        BorderRadius::MAX,
+        GlobalZIndex(i32::MAX - 1),
+        Node {
+            border: UiRect::all(Val::Px(2.)),
+            height: Val::Px(CURSOR_SIZE),
+            margin: UiRect {
+                left: Val::Px(-CURSOR_SIZE / 2.),
+                top: Val::Px(-CURSOR_SIZE / 2.),
+                ..default()
+            },
+            position_type: PositionType::Absolute,
+            width: Val::Px(CURSOR_SIZE),
+            ..default()
+        },
+        PickingBehavior::IGNORE,
+        StateScoped(Screen::InGame),
+        Visibility::Hidden,
+    ));
+}
+
+fn reset_game_cursor(mut cursor: ResMut<GameCursor>) {
+    *cursor = GameCursor::default();
+}
+
+// Touch input overrides this, see `touch`.
+fn read_select(action_state: Res<ActionState<GameAction>>, mut cursor: ResMut<GameCursor>) {
+    cursor.select_pressed = action_state.pressed(&GameAction::Select);
+    cursor.select_just_pressed = action_state.just_pressed(&GameAction::Select);
+}
+
+// Neither the virtual cursor nor touch can select through the UI, so only the mouse needs to worry
+// about it.
+pub fn track_mouse(
+    camera: Single<&Camera, With<MainCamera>>,
+    mut cursor: ResMut<GameCursor>,
+    hover_map: Res<HoverMap>,
+    mouse_motion: Res<AccumulatedMouseMotion>,
+    nodes: Query<(), With<Node>>,
+    window: Single<&Window>,
+) {
+    // Any mouse movement hands control back to the mouse.
+    if mouse_motion.delta != Vec2::ZERO {
+        cursor.source = CursorSource::Mouse;
+    }
+    if cursor.source != CursorSource::Mouse {
+        return;
+    }
+
+    cursor.viewport_position = window
+        .cursor_position()
+        .map(|pos| window_to_viewport(&camera, pos));
+    if pointer_over_ui(&hover_map, &nodes) {
+        cursor.select_pressed = false;
+        cursor.select_just_pressed = false;
+    }
+}
+
+fn move_virtual_cursor(
+    action_state: Res<ActionState<GameAction>>,
+    camera: Single<&Camera, With<MainCamera>>,
+    mut cursor: ResMut<GameCursor>,
+    time: Res<Time>,
+) {
+    let input = action_state
+        .axis_pair(&GameAction::MoveCursor)
+        .clamp_length_max(1.);
+    if input == Vec2::ZERO {
+        cursor.held_secs = 0.;
+        return;
+    }
+
+    let viewport_size = rq!(camera.logical_viewport_size());
+    if cursor.source != CursorSource::Gamepad {
+        cursor.source = CursorSource::Gamepad;
+        // Carry on from wherever the mouse was, if it was anywhere at all.
+        cursor.viewport_position = cursor.viewport_position.or(Some(viewport_size / 2.));
+    }
+
+    cursor.held_secs += time.delta_secs();
+    let speed = (CURSOR_MIN_SPEED + CURSOR_ACCELERATION * cursor.held_secs).min(CURSOR_MAX_SPEED);
+    // The stick has y pointing up, the viewport has it pointing down.
+    let delta = Vec2::new(input.x, -input.y) * speed * time.delta_secs();
+    let pos = cursor.viewport_position.unwrap_or_default() + delta;
+    cursor.viewport_position = Some(pos.clamp(Vec2::ZERO, viewport_size));
+}
+
+// Picking out a single Yup in a crowd with a stick is fiddly, so once the stick is let go, pull the
+// cursor onto the nearest Yup.
+fn snap_to_yups(
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut cursor: ResMut<GameCursor>,
+    time: Res<Time>,
+    yups: Query<&GlobalTransform, With<Yup>>,
+) {
+    if cursor.source != CursorSource::Gamepad || cursor.held_secs > 0. {
+        return;
+    }
+    let mut pos = rq!(cursor.viewport_position);
+
+    let (cam, cam_transform) = *camera;
+    let nearest = yups
+        .iter()
+        .filter_map(|t| cam.world_to_viewport(cam_transform, t.translation()).ok())
+        .min_by(|a, b| a.distance_squared(pos).total_cmp(&b.distance_squared(pos)));
+    let nearest = rq!(nearest.filter(|yup| yup.distance(pos) < SNAP_DISTANCE));
+
+    pos.smooth_nudge(&nearest, SNAP_DECAY_RATE, time.delta_secs());
+    cursor.viewport_position = Some(pos);
+}
+
+pub fn update_world_position(
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut cursor: ResMut<GameCursor>,
+) {
+    let (cam, cam_transform) = *camera;
+    cursor.world_position = cursor
+        .viewport_position
+        .and_then(|pos| cam.viewport_to_world_2d(cam_transform, pos).ok());
+}
+
+fn update_virtual_cursor(
+    cursor: Res<GameCursor>,
+    ui_scale: Res<UiScale>,
+    virtual_cursor: Single<(&mut Node, &mut Visibility), With<VirtualCursor>>,
+) {
+    let (mut node, mut visibility) = virtual_cursor.into_inner();
+    match cursor.viewport_position {
+        Some(pos) if cursor.source == CursorSource::Gamepad => {
+            // UI is laid out in scaled pixels, see `viewport`.
+            node.left = Val::Px(pos.x / ui_scale.0);
+            node.top = Val::Px(pos.y / ui_scale.0);
+            visibility.set_if_neq(Visibility::Inherited);
+        }
+        _ => {
+            visibility.set_if_neq(Visibility::Hidden);
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use bevy::prelude::*;
+use tiny_bail::prelude::*;
+
+use crate::{
+    GameSet,
+    assets::Levels,
+    audio::{PlaySfx, Sfx},
+    game::{
+        level::{definition::LevelDefinition, terrain_to_world},
+        particles::{ParticleEffect, SpawnParticles},
+        yup::{CharacterState, Yup, feet},
+    },
+    screens::Screen,
+};
+
+// The area around each exit point that takes a Yup home, in world units.
+const EXIT_SIZE: Vec2 = Vec2::new(16., 24.);
+
+pub fn plugin(app: &mut App) {
+    app.add_event::<YupRescued>();
+    app.add_systems(OnEnter(Screen::InGame), spawn_exits.in_set(GameSet::Init));
+    app.add_systems(Update, rescue_yups.in_set(GameSet::Update));
+}
+
+/// Where Yups are trying to get to.
+#[derive(Component, Debug)]
+pub struct Exit {
+    /// In world coordinates.
+    pub area: Rect,
+}
+
+/// Sent when a Yup reaches an exit, at the position they were rescued from.
+#[derive(Event, Debug)]
+pub struct YupRescued {
+    pub position: Vec2,
+}
+
+fn spawn_exits(
+    mut commands: Commands,
+    definitions: Res<Assets<LevelDefinition>>,
+    images: Res<Assets<Image>>,
+    levels: Res<Levels>,
+) {
+    let definition = r!(definitions.get(&levels.first));
+    let terrain_size = r!(images.get(&definition.terrain_image)).size_f32();
+
+    for position in &definition.exits {
+        let centre = terrain_to_world(terrain_size, *position);
+        commands.spawn((
+            Name::new("Exit"),
+            Exit {
+                area: Rect::from_center_size(centre, EXIT_SIZE),
+            },
+            Transform::from_translation(centre.extend(0.)),
+            StateScoped(Screen::InGame),
+        ));
+    }
+}
+
+fn rescue_yups(
+    mut commands: Commands,
+    exits: Query<&Exit>,
+    mut particles: EventWriter<SpawnParticles>,
+    mut rescues: EventWriter<YupRescued>,
+    mut sfx: EventWriter<PlaySfx>,
+    yups: Query<(Entity, &CharacterState, &Transform), With<Yup>>,
+) {
+    for (entity, state, transform) in &yups {
+        if matches!(state, CharacterState::Dead(_)) {
+            continue;
+        }
+        // Yups are home once their feet are through the door.
+        let feet = feet(transform);
+        if !exits.iter().any(|exit| exit.area.contains(feet)) {
+            continue;
+        }
+
+        commands.entity(entity).despawn_recursive();
+        rescues.send(YupRescued { position: feet });
+        particles.send(SpawnParticles {
+            effect: ParticleEffect::Sparkle,
+            position: feet,
+        });
+        sfx.send(PlaySfx::new(Sfx::Rescue).at(feet));
+    }
+}
@@ -0,0 +1,225 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+use tiny_bail::prelude::*;
+
+use crate::{
+    GameSet,
+    assets::Levels,
+    game::{
+        Game,
+        level::{definition::LevelDefinition, terrain_to_world},
+        replay::Playback,
+        yup::SpawnYup,
+    },
+    input::GameAction,
+    screens::Screen,
+    ui::Widgets,
+};
+
+pub const RELEASE_RATE_MAX: u32 = 99;
+pub const RELEASE_RATE_MIN: u32 = 1;
+// Each press of a key or button changes the release rate by this much.
+const RELEASE_RATE_STEP: u32 = 5;
+// Time between Yups at the slowest and fastest release rates.
+const SLOWEST_INTERVAL_SECS: f32 = 4.;
+const FASTEST_INTERVAL_SECS: f32 = 0.25;
+
+pub fn plugin(app: &mut App) {
+    app.add_event::<ReleaseRateChanged>();
+    app.init_resource::<ReleaseRate>();
+    app.add_systems(
+        OnEnter(Screen::InGame),
+        (init_release, spawn_release_controls).in_set(GameSet::Init),
+    );
+    app.add_systems(OnExit(Screen::InGame), remove_release);
+    app.add_systems(
+        Update,
+        (
+            tick_release_timer.in_set(GameSet::TickTimers),
+            change_release_rate
+                .in_set(GameSet::RecordInput)
+                .run_if(not(resource_exists::<Playback>)),
+            (apply_release_rate, release_yups, update_release_text)
+                .chain()
+                .in_set(GameSet::Update),
+        )
+            .run_if(resource_exists::<Release>),
+    );
+}
+
+/// How quickly Yups come out of the hatches, from [`RELEASE_RATE_MIN`] to [`RELEASE_RATE_MAX`].
+#[derive(Resource, Debug, Default)]
+pub struct ReleaseRate {
+    pub current: u32,
+    /// Set by the level. The player can't go any slower than this.
+    pub min: u32,
+}
+
+impl ReleaseRate {
+    pub fn faster(&mut self) {
+        self.current = (self.current + RELEASE_RATE_STEP).min(RELEASE_RATE_MAX);
+    }
+
+    pub fn slower(&mut self) {
+        self.current = self.current.saturating_sub(RELEASE_RATE_STEP).max(self.min);
+    }
+
+    /// The time between one Yup and the next.
+    pub fn interval_secs(&self) -> f32 {
+        let t = self.current.saturating_sub(RELEASE_RATE_MIN) as f32
+            / (RELEASE_RATE_MAX - RELEASE_RATE_MIN) as f32;
+        SLOWEST_INTERVAL_SECS.lerp(FASTEST_INTERVAL_SECS, t.clamp(0., 1.))
+    }
+}
+
+/// Sent whenever the release rate changes, including when the level starts. Each one is recorded
+/// in the level's replay, so that it's played back at the same moment.
+#[derive(Event, Debug)]
+pub struct ReleaseRateChanged {
+    pub rate: u32,
+}
+
+/// The Yups still to come out of the hatches. Only present during a level.
+#[derive(Resource, Debug)]
+pub struct Release {
+    /// World positions, taken in turn.
+    hatches: Vec<Vec2>,
+    next_hatch: usize,
+    released: u32,
+    timer: Timer,
+    total: u32,
+}
+
+#[derive(Component, Debug)]
+struct ReleaseRateText;
+
+fn init_release(
+    mut commands: Commands,
+    definitions: Res<Assets<LevelDefinition>>,
+    images: Res<Assets<Image>>,
+    levels: Res<Levels>,
+    mut rate: ResMut<ReleaseRate>,
+) {
+    let definition = r!(definitions.get(&levels.first));
+    let terrain_size = r!(images.get(&definition.terrain_image)).size_f32();
+
+    *rate = ReleaseRate {
+        current: definition.release_rate,
+        min: definition.release_rate,
+    };
+    commands.insert_resource(Release {
+        hatches: definition
+            .hatches
+            .iter()
+            .map(|hatch| terrain_to_world(terrain_size, *hatch))
+            .collect(),
+        next_hatch: 0,
+        released: 0,
+        timer: Timer::from_seconds(rate.interval_secs(), TimerMode::Repeating),
+        total: definition.yups,
+    });
+}
+
+fn remove_release(mut commands: Commands) {
+    commands.remove_resource::<Release>();
+}
+
+fn spawn_release_controls(mut commands: Commands) {
+    commands
+        .spawn((
+            Name::new("Release Controls"),
+            Node {
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(8.),
+                position_type: PositionType::Absolute,
+                right: Val::Px(10.),
+                top: Val::Px(10.),
+                ..default()
+            },
+            StateScoped(Screen::InGame),
+        ))
+        .with_children(|p| {
+            // A replay being played back sets the release rate itself.
+            p.button("-").observe(
+                |_trigger: Trigger<Pointer<Click>>,
+                 game: Res<State<Game>>,
+                 playback: Option<Res<Playback>>,
+                 mut rate: ResMut<ReleaseRate>| {
+                    if *game.get() == Game::Playing && playback.is_none() {
+                        rate.slower();
+                    }
+                },
+            );
+            p.spawn((
+                Name::new("Release Rate Text"),
+                ReleaseRateText,
+                Text::default(),
+            ));
+            p.button("+").observe(
+                |_trigger: Trigger<Pointer<Click>>,
+                 game: Res<State<Game>>,
+                 playback: Option<Res<Playback>>,
+                 mut rate: ResMut<ReleaseRate>| {
+                    if *game.get() == Game::Playing && playback.is_none() {
+                        rate.faster();
+                    }
+                },
+            );
+        });
+}
+
+fn tick_release_timer(mut release: ResMut<Release>, time: Res<Time>) {
+    release.timer.tick(time.delta());
+}
+
+fn change_release_rate(action_state: Res<ActionState<GameAction>>, mut rate: ResMut<ReleaseRate>) {
+    if action_state.just_pressed(&GameAction::ReleaseFaster) {
+        rate.faster();
+    } else if action_state.just_pressed(&GameAction::ReleaseSlower) {
+        rate.slower();
+    }
+}
+
+// Takes effect straight away: a Yup that was due soon at the old rate may now be overdue, and
+// comes out at once.
+pub fn apply_release_rate(
+    mut changes: EventWriter<ReleaseRateChanged>,
+    rate: Res<ReleaseRate>,
+    mut release: ResMut<Release>,
+) {
+    if !rate.is_changed() {
+        return;
+    }
+    let interval = Duration::from_secs_f32(rate.interval_secs());
+    release.timer.set_duration(interval);
+    changes.send(ReleaseRateChanged { rate: rate.current });
+}
+
+fn release_yups(mut release: ResMut<Release>, mut spawns: EventWriter<SpawnYup>) {
+    if release.hatches.is_empty() {
+        return;
+    }
+    // The first Yup comes out as soon as the level starts. After that, a long frame can take in
+    // several intervals at a fast release rate, and each of them lets out a Yup.
+    let due = release.timer.times_finished_this_tick() + u32::from(release.released == 0);
+
+    for _ in 0..due.min(release.total.saturating_sub(release.released)) {
+        let hatch = release.next_hatch % release.hatches.len();
+        spawns.send(SpawnYup {
+            position: release.hatches[hatch],
+        });
+        release.next_hatch = hatch + 1;
+        release.released += 1;
+    }
+}
+
+fn update_release_text(rate: Res<ReleaseRate>, mut texts: Query<&mut Text, With<ReleaseRateText>>) {
+    if !rate.is_changed() {
+        return;
+    }
+    for mut text in &mut texts {
+        text.0 = format!("Release rate: {}", rate.current);
+    }
+}
@@ -0,0 +1,229 @@
+use bevy::{prelude::*, sprite::Anchor};
+use serde::{Deserialize, Serialize};
+use tiny_bail::prelude::*;
+
+use crate::{
+    GameSet,
+    assets::{Hazards, Levels},
+    audio::{PlaySfx, Sfx},
+    game::{
+        Game,
+        animation::{SpriteAnimation, sheet::AnimationSheet},
+        level::{definition::LevelDefinition, terrain_to_world},
+        yup::{CharacterState, Death, Yup},
+    },
+    screens::Screen,
+};
+
+// Crushers and spikes go round a cycle of this length, and are only dangerous for part of it.
+const CYCLE_SECS: f32 = 2.;
+// How long a Yup lingers after meeting their end.
+const DEATH_SECS: f32 = 1.;
+// In front of the terrain (and any darkness), behind the Yups.
+const HAZARD_Z: f32 = 0.8;
+// The fraction of the cycle that crushers spend slammed down.
+const SLAM_FRACTION: f32 = 0.25;
+// The fraction of the cycle that spikes spend poking out.
+const SPIKES_FRACTION: f32 = 0.5;
+
+pub fn plugin(app: &mut App) {
+    app.add_event::<YupDied>();
+    app.add_systems(OnEnter(Screen::InGame), spawn_hazards.in_set(GameSet::Init));
+    app.add_systems(
+        Update,
+        (animate_hazards, start_dying).in_set(GameSet::Update),
+    );
+    app.add_systems(FixedUpdate, remove_dead.run_if(in_state(Game::Playing)));
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum HazardKind {
+    /// Slams down every so often, crushing whoever is underneath.
+    Crusher,
+    Lava,
+    /// Pokes out every so often.
+    Spikes,
+    /// Drowns any Yup who can't swim.
+    Water,
+}
+
+impl HazardKind {
+    /// Whether the hazard is dangerous at the given elapsed game time. Crushers and spikes come
+    /// and go, while lava and water never let up.
+    pub fn is_active(&self, elapsed_secs: f32) -> bool {
+        self.extent(elapsed_secs) >= 1.
+    }
+
+    /// How the hazard does a Yup in.
+    pub fn death(&self) -> Death {
+        match self {
+            Self::Crusher => Death::Crushed,
+            Self::Lava => Death::Burned,
+            Self::Spikes => Death::Impaled,
+            Self::Water => Death::Drowned,
+        }
+    }
+
+    /// The name of the animation clip the hazard plays, from `assets/animations/hazards.anim.ron`.
+    pub fn clip(&self) -> &'static str {
+        match self {
+            Self::Crusher => "crusher",
+            Self::Lava => "lava",
+            Self::Spikes => "spikes",
+            Self::Water => "water",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Self::Crusher => Color::srgb(0.45, 0.45, 0.5),
+            Self::Lava => Color::srgb(1., 0.35, 0.05),
+            Self::Spikes => Color::srgb(0.75, 0.75, 0.8),
+            Self::Water => Color::srgba(0.2, 0.4, 0.9, 0.7),
+        }
+    }
+
+    /// How far out a crusher or spikes are, from 0 (tucked away) to 1 (fully out, and deadly).
+    fn extent(&self, elapsed_secs: f32) -> f32 {
+        let cycle = (elapsed_secs % CYCLE_SECS) / CYCLE_SECS;
+        let fraction = match self {
+            Self::Crusher => SLAM_FRACTION,
+            Self::Spikes => SPIKES_FRACTION,
+            Self::Lava | Self::Water => return 1.,
+        };
+        if cycle < fraction {
+            1.
+        } else {
+            // Withdraw slowly over the rest of the cycle, ready to strike again.
+            1. - (cycle - fraction) / (1. - fraction)
+        }
+    }
+}
+
+/// A dangerous part of the level.
+#[derive(Component, Debug)]
+pub struct Hazard {
+    /// In world coordinates.
+    pub area: Rect,
+    pub kind: HazardKind,
+}
+
+/// Sent when a Yup meets their end, at the position they died.
+#[derive(Event, Debug)]
+pub struct YupDied {
+    pub death: Death,
+    pub position: Vec2,
+}
+
+/// Counts down until a dead Yup is removed.
+#[derive(Component, Debug, Deref, DerefMut)]
+struct DeathTimer(Timer);
+
+/// The first active hazard containing the given position, if any.
+pub fn hazard_at<'a>(
+    hazards: impl IntoIterator<Item = &'a Hazard>,
+    position: Vec2,
+    elapsed_secs: f32,
+) -> Option<HazardKind> {
+    hazards
+        .into_iter()
+        .find(|hazard| hazard.area.contains(position) && hazard.kind.is_active(elapsed_secs))
+        .map(|hazard| hazard.kind)
+}
+
+fn spawn_hazards(
+    mut commands: Commands,
+    definitions: Res<Assets<LevelDefinition>>,
+    hazard_sheet: Res<Hazards>,
+    images: Res<Assets<Image>>,
+    levels: Res<Levels>,
+    sheets: Res<Assets<AnimationSheet>>,
+) {
+    let definition = r!(definitions.get(&levels.first));
+    let terrain_size = r!(images.get(&definition.terrain_image)).size_f32();
+    let sheet = r!(sheets.get(&hazard_sheet.sheet));
+
+    for hazard in &definition.hazards {
+        let area = Rect::from_corners(
+            terrain_to_world(terrain_size, hazard.position),
+            terrain_to_world(terrain_size, hazard.position + hazard.size),
+        );
+        // Crushers come down from the top, spikes up from the bottom.
+        let (anchor, origin) = match hazard.kind {
+            HazardKind::Crusher => (Anchor::TopCenter, Vec2::new(area.center().x, area.max.y)),
+            HazardKind::Spikes => (Anchor::BottomCenter, Vec2::new(area.center().x, area.min.y)),
+            HazardKind::Lava | HazardKind::Water => (Anchor::Center, area.center()),
+        };
+        commands.spawn((
+            Name::new(format!("{:?} Hazard", hazard.kind)),
+            Hazard {
+                area,
+                kind: hazard.kind,
+            },
+            SpriteAnimation::new(hazard_sheet.sheet.clone(), hazard.kind.clip()),
+            // Each frame is stretched to fill the hazard.
+            Sprite {
+                anchor,
+                color: hazard.kind.color(),
+                custom_size: Some(area.size()),
+                ..Sprite::from_atlas_image(sheet.image_handle.clone(), TextureAtlas {
+                    index: 0,
+                    layout: sheet.layout.clone(),
+                })
+            },
+            Transform::from_translation(origin.extend(HAZARD_Z)),
+            StateScoped(Screen::InGame),
+        ));
+    }
+}
+
+// Water and lava only need their animation, but crushers and spikes also come and go, in time with
+// when they're dangerous.
+fn animate_hazards(mut hazards: Query<(&Hazard, &mut Sprite)>, time: Res<Time>) {
+    let elapsed_secs = time.elapsed_secs();
+    for (hazard, mut sprite) in &mut hazards {
+        if !matches!(hazard.kind, HazardKind::Crusher | HazardKind::Spikes) {
+            continue;
+        }
+        let extent = hazard.kind.extent(elapsed_secs);
+        let height = hazard.area.height() * extent.max(0.1);
+        sprite.custom_size = Some(Vec2::new(hazard.area.width(), height));
+    }
+}
+
+fn start_dying(
+    mut commands: Commands,
+    mut deaths: EventWriter<YupDied>,
+    mut sfx: EventWriter<PlaySfx>,
+    yups: Query<
+        (Entity, &CharacterState, &GlobalTransform),
+        (With<Yup>, Changed<CharacterState>, Without<DeathTimer>),
+    >,
+) {
+    for (entity, state, transform) in &yups {
+        let CharacterState::Dead(death) = state else {
+            continue;
+        };
+        let position = transform.translation().truncate();
+        commands
+            .entity(entity)
+            .insert(DeathTimer(Timer::from_seconds(DEATH_SECS, TimerMode::Once)));
+        deaths.send(YupDied {
+            death: *death,
+            position,
+        });
+        sfx.send(PlaySfx::new(Sfx::Splat).at(position));
+    }
+}
+
+fn remove_dead(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut yups: Query<(Entity, &mut DeathTimer)>,
+) {
+    for (entity, mut timer) in &mut yups {
+        if timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
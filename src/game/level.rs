@@ -1,3 +1,5 @@
+pub mod definition;
+
 use bevy::{
     asset::RenderAssetUsages,
     prelude::*,
@@ -11,38 +13,110 @@ use bevy::{
 use tiny_bail::prelude::*;
 
 use crate::{
-    GameSet, MainCamera, assets::Masks, physics::collision::CollisionsTerrain, screens::Screen,
+    GameSet,
+    assets::Masks,
+    audio::{PlaySfx, Sfx},
+    game::{
+        cursor::GameCursor,
+        objects::{SolidObjects, stamp_solid_objects},
+        replay,
+        skills::{SelectedSkill, Skill},
+        steel::{Steel, touches_steel},
+    },
+    physics::collision::CollisionsTerrain,
+    screens::Screen,
 };
 
 use super::rendering::GameRenderLayers;
+use definition::{LevelDefinition, LevelDefinitionLoader};
 
 const SHADER_ASSET_PATH: &str = "shaders/terrain.wgsl";
+// Radius of the hole made in the terrain by clicking on it during play.
+const DIG_RADIUS: f32 = 10.;
 
 pub fn plugin(app: &mut App) {
     app.add_plugins(Material2dPlugin::<LevelMaterial>::default());
+    app.init_asset::<LevelDefinition>();
+    app.init_asset_loader::<LevelDefinitionLoader>();
+    app.add_event::<TerrainErased>();
     app.add_systems(
         OnEnter(Screen::InGame),
         (init, init_compute_shader).chain().in_set(GameSet::Init),
     );
-    app.add_systems(Update, update_cursor_position.in_set(GameSet::RecordInput));
+    app.add_systems(
+        Update,
+        update_cursor_position
+            .in_set(GameSet::RecordInput)
+            .after(replay::play_back),
+    );
     app.add_systems(
         RunFixedMainLoop,
         swap_textures
             .in_set(RunFixedMainLoopSystem::AfterFixedMainLoop)
-            .run_if(in_state(Screen::InGame)),
+            .run_if(in_state(Screen::InGame).or(in_state(Screen::Editor))),
     );
 }
 
 #[derive(Component, Debug)]
-pub struct Level;
+pub struct Level {
+    /// Size of the terrain in pixels, which is also its size in world units.
+    pub size: Vec2,
+}
 
 #[derive(Component)]
 pub struct LevelCamera;
 
+/// Sent every frame that the terrain is dug away around a point in the world.
+#[derive(Event, Debug)]
+pub struct TerrainErased {
+    pub position: Vec2,
+    /// In terrain pixels, which are the same size as world units.
+    pub radius: f32,
+}
+
+/// Converts a position in world coordinates to terrain pixel coordinates for a level of the given
+/// size, centred on the origin. The inverse of [`terrain_to_world`].
+pub fn world_to_terrain(terrain_size: Vec2, position: Vec2) -> Vec2 {
+    Vec2::new(
+        position.x + terrain_size.x / 2.,
+        terrain_size.y / 2. - position.y,
+    )
+}
+
+/// Converts a position in terrain pixel coordinates (origin top left, y down) to world coordinates
+/// for a level of the given size, centred on the origin.
+pub fn terrain_to_world(terrain_size: Vec2, position: Vec2) -> Vec2 {
+    Vec2::new(
+        position.x - terrain_size.x / 2.,
+        terrain_size.y / 2. - position.y,
+    )
+}
+
+/// What the terrain shader does to the terrain under the cursor.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Brush {
+    #[default]
+    None = 0,
+    Erase = 1,
+    Paint = 2,
+}
+
 #[derive(Asset, Default, TypePath, AsBindGroup, Debug, Clone)]
 pub struct LevelMaterial {
     #[uniform(0)]
     pub cursor_position: Vec2,
+    /// A [`Brush`], as understood by the shader.
+    #[uniform(5)]
+    pub brush: u32,
+    /// Only used by [`Brush::Paint`].
+    #[uniform(6)]
+    pub brush_color: LinearRgba,
+    /// In terrain pixels.
+    #[uniform(7)]
+    pub brush_radius: f32,
+    /// Size of the terrain image, in pixels.
+    #[uniform(8)]
+    pub mesh_dimensions: Vec2,
     #[texture(1)]
     #[sampler(2)]
     pub terrain_texture: Handle<Image>,
@@ -77,25 +151,31 @@ pub fn init(
     masks: Res<Masks>,
     mut materials: ResMut<Assets<LevelMaterial>>,
     mut meshes: ResMut<Assets<Mesh>>,
-    window: Single<&Window>,
+    screen: Res<State<Screen>>,
 ) {
-    let cursor_position = r!(window.physical_cursor_position());
     let level_image = r!(images.get(&level_targets.source));
 
     commands.spawn((
         Name::new("Level"),
-        Level,
+        Level {
+            size: level_image.size_f32(),
+        },
         Mesh2d(meshes.add(Rectangle::new(
             level_image.size().x as f32,
             level_image.size().y as f32,
         ))),
         MeshMaterial2d(materials.add(LevelMaterial {
-            cursor_position,
+            // Nothing is dug until the player asks for it.
+            brush: Brush::None as u32,
+            brush_radius: DIG_RADIUS,
             mask_texture: masks.cursor.clone(),
+            mesh_dimensions: level_image.size_f32(),
             terrain_texture: level_targets.source.clone(),
+            ..default()
         })),
         RenderLayers::layer(GameRenderLayers::Terrain.into()),
-        StateScoped(Screen::InGame),
+        // The level is shared between gameplay and the editor.
+        StateScoped(screen.get().clone()),
     ));
 
     commands.spawn((
@@ -111,7 +191,7 @@ pub fn init(
         // Only the level background lives on render layer 1, everything else is rendered normally
         // including sprites, etc.
         RenderLayers::layer(GameRenderLayers::Terrain.into()),
-        StateScoped(Screen::InGame),
+        StateScoped(screen.get().clone()),
     ));
 }
 
@@ -135,42 +215,45 @@ fn init_compute_shader(
     *collisions_terrain = CollisionsTerrain(images.add(collisions_terrain_image));
 }
 
-// TODO: don't run this unless in game
+// Digs into the terrain under the cursor while selecting with the Dig skill, unless there's steel
+// in the way.
 fn update_cursor_position(
-    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
-    level: Query<(&MeshMaterial2d<LevelMaterial>, &Transform), With<Level>>,
+    cursor: Res<GameCursor>,
+    mut erased: EventWriter<TerrainErased>,
+    level: Query<(&Level, &MeshMaterial2d<LevelMaterial>, &Transform)>,
     mut materials: ResMut<Assets<LevelMaterial>>,
-    mouse_button: Res<ButtonInput<MouseButton>>,
-    window: Single<&Window>,
+    selected: Res<SelectedSkill>,
+    mut sfx: EventWriter<PlaySfx>,
+    steel: Query<&Steel>,
 ) {
-    if !mouse_button.pressed(MouseButton::Left) {
-        return;
-    }
-
-    let (cam, cam_transform) = *camera;
-    let (material_handle, material_transform) = rq!(level.get_single());
+    let (level, material_handle, material_transform) = rq!(level.get_single());
     let level_material = rq!(materials.get_mut(&material_handle.0));
 
-    if let Some(cursor_pos) = window.cursor_position() {
-        // Convert the cursor pos to world coords. So, for the centre of the window, (640, 360)
-        // will become (0, 0). Note that this flips the y value in Bevy, so we'll need to flip it
-        // again later. This step should allow us to scroll the image and still get a reliable
-        // cursor position.
-        let world_pos = r!(cam.viewport_to_world_2d(cam_transform, cursor_pos));
-
-        // Convert the world pos to pixel coords within the mesh texture.
-        let texture_pos = material_transform
-            .compute_matrix()
-            .inverse()
-            .transform_point3(world_pos.extend(0.));
-
-        // This final step is necessary to offset the position passed to the shader by the window
-        // dimensions. Without it, we'll get a "ghost image" showing in the bottom right corner of
-        // the window when the shader draws to the centre of it! We also invert the y value again.
-        level_material.cursor_position = Vec2::new(
-            texture_pos.x + window.width(),
-            -texture_pos.y + window.height(),
-        );
+    let digging = **selected == Skill::Dig && cursor.select_pressed;
+    match cursor.world_position {
+        Some(world_pos)
+            if digging && !touches_steel(&steel, world_pos, level_material.brush_radius) =>
+        {
+            // Convert the world pos to coords relative to the centre of the level mesh.
+            let mesh_pos = material_transform
+                .compute_matrix()
+                .inverse()
+                .transform_point3(world_pos.extend(0.));
+
+            if level_material.brush != Brush::Erase as u32 {
+                sfx.send(PlaySfx::new(Sfx::Dig).at(world_pos));
+            }
+            erased.send(TerrainErased {
+                position: world_pos,
+                radius: level_material.brush_radius,
+            });
+
+            // Finally, offset by half the level size and flip the y value to get the pixel coords
+            // within the terrain texture that the shader expects.
+            level_material.brush = Brush::Erase as u32;
+            level_material.cursor_position = world_to_terrain(level.size, mesh_pos.truncate());
+        }
+        _ => level_material.brush = Brush::None as u32,
     }
 }
 
@@ -178,11 +261,12 @@ fn swap_textures(
     mut cam: Single<&mut Camera, With<LevelCamera>>,
     collisions_terrain: ResMut<CollisionsTerrain>,
     mut images: ResMut<Assets<Image>>,
-    level: Query<&MeshMaterial2d<LevelMaterial>, With<Level>>,
+    level: Query<(&Level, &MeshMaterial2d<LevelMaterial>)>,
     mut materials: ResMut<Assets<LevelMaterial>>,
     mut level_targets: ResMut<LevelRenderTargets>,
+    solids: Res<SolidObjects>,
 ) {
-    let l = r!(level.get_single());
+    let (level, l) = r!(level.get_single());
     let level_material = r!(materials.get_mut(&l.0));
 
     // Create a clone of the current destination render target, to use as a source of truth for
@@ -194,6 +278,8 @@ fn swap_textures(
     collisions_terrain_image.texture_descriptor.format = TextureFormat::Rgba8Unorm;
     collisions_terrain_image.texture_descriptor.usage |=
         TextureUsages::COPY_SRC | TextureUsages::STORAGE_BINDING;
+    // Doors, bridges and the like aren't part of the terrain, but Yups still need to bump into them.
+    stamp_solid_objects(&mut collisions_terrain_image, level.size, &solids);
     images.insert(&collisions_terrain.0, collisions_terrain_image);
 
     // Swap the camera target and fragment shader source.
@@ -0,0 +1,312 @@
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+    utils::HashMap,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    dialogue::DialogueLine,
+    game::{
+        hatch::{RELEASE_RATE_MAX, RELEASE_RATE_MIN},
+        hazards::HazardKind,
+        objects::ObjectKind,
+        skills::Skill,
+        triggers::{Action, Condition},
+    },
+    physics::collision::YUP_COUNT,
+};
+
+/// Everything needed to build a level, as described by a `.level.ron` file in `assets/levels`.
+///
+/// All positions are in terrain pixel coordinates, i.e. with the origin at the top left of the
+/// terrain image, which is how designers will think about them when painting a level.
+#[derive(Asset, TypePath, Debug, Clone, Serialize, Deserialize)]
+pub struct LevelDefinition {
+    pub name: String,
+    pub designer: String,
+    /// Asset path of the terrain image.
+    pub terrain: String,
+    /// The terrain image itself, loaded as a dependency of the definition so that both are ready
+    /// by the time the loading screen completes.
+    #[serde(skip)]
+    #[dependency]
+    pub terrain_image: Handle<Image>,
+    /// Total number of Yups released over the course of the level.
+    pub yups: u32,
+    /// How many Yups must make it home for the level to be complete.
+    pub rescue_target: u32,
+    /// How quickly Yups come out of the hatches to start with, from 1 to 99. The player can speed
+    /// it up, but never slow it down any further.
+    #[serde(default = "default_release_rate")]
+    pub release_rate: u32,
+    /// How many times each skill can be given out. Skills that aren't listed never run out, and
+    /// neither does digging, which is the player's own. List a skill with 0 to leave it out of the
+    /// level.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub skills: HashMap<Skill, u32>,
+    /// How long the player has to rescue the Yups, if there's any limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_limit_secs: Option<f32>,
+    /// Where Yups enter the level.
+    pub hatches: Vec<Vec2>,
+    /// Where Yups leave the level.
+    pub exits: Vec<Vec2>,
+    /// Said on the intro screen, before the level starts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub intro: Vec<DialogueLine>,
+    /// Dark levels are only lit around Yups, hatches, exits and torches.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dark: bool,
+    /// Dangerous parts of the level.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hazards: Vec<HazardArea>,
+    /// Parts of the terrain that can't be dug through.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steel: Vec<SteelArea>,
+    /// Switches, doors, bridges and elevators.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub objects: Vec<ObjectDefinition>,
+    /// Scripted events, such as opening a door once enough Yups have been rescued.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub triggers: Vec<TriggerDefinition>,
+    /// Extra lights for dark levels.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub torches: Vec<Vec2>,
+    /// Images shown behind the terrain, from the furthest back to the nearest.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub backgrounds: Vec<BackgroundLayer>,
+    /// The background images themselves, in the same order as `backgrounds`.
+    #[serde(skip)]
+    #[dependency]
+    pub background_images: Vec<Handle<Image>>,
+    /// Asset path of the music to play, if not the usual level music.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub music: Option<String>,
+}
+
+/// An image behind the terrain which scrolls at its own pace, making it look further away.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackgroundLayer {
+    /// Asset path of the image, which repeats horizontally across the level.
+    pub image: String,
+    /// How far the layer moves when the camera does: 0 stays put on the screen like a distant sky,
+    /// 1 moves along with the terrain.
+    pub factor: f32,
+    /// How far the middle of the image is from the middle of the level, in terrain pixels.
+    #[serde(default)]
+    pub offset: Vec2,
+}
+
+/// A rectangular hazard, such as a pool of water.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HazardArea {
+    pub kind: HazardKind,
+    /// The top left corner.
+    pub position: Vec2,
+    pub size: Vec2,
+}
+
+/// A rectangle of terrain that can't be dug through.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SteelArea {
+    /// The top left corner.
+    pub position: Vec2,
+    pub size: Vec2,
+}
+
+fn default_release_rate() -> u32 {
+    (RELEASE_RATE_MIN + RELEASE_RATE_MAX) / 2
+}
+
+/// An interactive object, such as a door and the pressure plate that opens it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectDefinition {
+    /// How other objects refer to this one.
+    #[serde(default)]
+    pub id: String,
+    pub kind: ObjectKind,
+    /// The top left corner.
+    pub position: Vec2,
+    pub size: Vec2,
+    /// Whether the object starts out active. What that means depends on the kind of object.
+    #[serde(default)]
+    pub active: bool,
+}
+
+/// Does each of the actions, in order, as soon as the condition is met.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriggerDefinition {
+    pub when: Condition,
+    pub then: Vec<Action>,
+}
+
+impl LevelDefinition {
+    /// How many times the skill can be given out, or `None` if there's no limit.
+    pub fn skill_count(&self, skill: Skill) -> Option<u32> {
+        match skill {
+            Skill::Dig => None,
+            Skill::Block | Skill::Swim => self.skills.get(&skill).copied(),
+        }
+    }
+
+    /// Checks the definition is internally consistent, given the size of its terrain image. An
+    /// empty result means the level is playable, if not necessarily winnable!
+    pub fn validate(&self, terrain_size: UVec2) -> Vec<LevelProblem> {
+        let mut problems = vec![];
+
+        if self.yups > YUP_COUNT as u32 {
+            problems.push(LevelProblem::TooManyYups {
+                yups: self.yups,
+                max: YUP_COUNT as u32,
+            });
+        }
+        if self.rescue_target > self.yups {
+            problems.push(LevelProblem::UnreachableTarget {
+                target: self.rescue_target,
+                yups: self.yups,
+            });
+        }
+        if !(RELEASE_RATE_MIN..=RELEASE_RATE_MAX).contains(&self.release_rate) {
+            problems.push(LevelProblem::InvalidReleaseRate {
+                rate: self.release_rate,
+            });
+        }
+        if self.hatches.is_empty() {
+            problems.push(LevelProblem::NoHatches);
+        }
+        if self.exits.is_empty() {
+            problems.push(LevelProblem::NoExits);
+        }
+        // Layers are only as wide as the terrain, so any other factor slides them out of view.
+        for layer in &self.backgrounds {
+            if !(0. ..=1.).contains(&layer.factor) {
+                problems.push(LevelProblem::InvalidBackgroundFactor {
+                    image: layer.image.clone(),
+                    factor: layer.factor,
+                });
+            }
+        }
+
+        // Areas have to fit entirely within the terrain, so both their corners are checked.
+        let bounds = Rect::from_corners(Vec2::ZERO, terrain_size.as_vec2());
+        let areas = self
+            .hazards
+            .iter()
+            .map(|h| ("hazard", h.position, h.size))
+            .chain(self.steel.iter().map(|s| ("steel", s.position, s.size)))
+            .chain(self.objects.iter().map(|o| ("object", o.position, o.size)));
+        let positions = self
+            .hatches
+            .iter()
+            .map(|p| ("hatch", *p))
+            .chain(self.exits.iter().map(|p| ("exit", *p)))
+            .chain(self.torches.iter().map(|p| ("torch", *p)))
+            .chain(
+                areas
+                    .flat_map(|(kind, position, size)| [(kind, position), (kind, position + size)]),
+            )
+            .chain(self.triggers.iter().flat_map(|t| {
+                let actions = t.then.iter().filter_map(Action::position).copied();
+                t.when
+                    .corners()
+                    .into_iter()
+                    .flatten()
+                    .chain(actions)
+                    .map(|p| ("trigger", p))
+            }));
+        for (kind, position) in positions {
+            if !bounds.contains(position) {
+                problems.push(LevelProblem::OutOfBounds {
+                    kind,
+                    position,
+                    size: terrain_size,
+                });
+            }
+        }
+
+        let targets = self
+            .objects
+            .iter()
+            .filter_map(|o| Some((format!("object {:?}", o.id), o.kind.target()?)))
+            .chain(self.triggers.iter().enumerate().flat_map(|(i, t)| {
+                t.then
+                    .iter()
+                    .filter_map(move |a| Some((format!("trigger {i}"), a.object()?)))
+            }));
+        for (by, target) in targets {
+            if !self.objects.iter().any(|o| o.id == target) {
+                problems.push(LevelProblem::UnknownTarget {
+                    by,
+                    target: target.to_string(),
+                });
+            }
+        }
+
+        problems
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum LevelProblem {
+    #[error("{kind} at {position} lies outside the {size} terrain")]
+    OutOfBounds {
+        kind: &'static str,
+        position: Vec2,
+        size: UVec2,
+    },
+    #[error("background {image:?} has a factor of {factor}, which isn't between 0 and 1")]
+    InvalidBackgroundFactor { image: String, factor: f32 },
+    #[error("release rate of {rate} isn't between {RELEASE_RATE_MIN} and {RELEASE_RATE_MAX}")]
+    InvalidReleaseRate { rate: u32 },
+    #[error("no exits, so no Yups can ever be rescued")]
+    NoExits,
+    #[error("no hatches, so no Yups will ever be released")]
+    NoHatches,
+    #[error("{yups} Yups exceeds the maximum of {max}")]
+    TooManyYups { yups: u32, max: u32 },
+    #[error("rescue target of {target} exceeds the {yups} Yups released")]
+    UnreachableTarget { target: u32, yups: u32 },
+    #[error("{by} acts on object {target:?}, which isn't in the level")]
+    UnknownTarget { by: String, target: String },
+}
+
+#[derive(Default)]
+pub struct LevelDefinitionLoader;
+
+#[derive(Debug, Error)]
+pub enum LevelDefinitionLoaderError {
+    #[error("could not read level definition: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse level definition: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+}
+
+impl AssetLoader for LevelDefinitionLoader {
+    type Asset = LevelDefinition;
+    type Settings = ();
+    type Error = LevelDefinitionLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let mut definition: LevelDefinition = ron::de::from_bytes(&bytes)?;
+        definition.terrain_image = load_context.load(definition.terrain.clone());
+        definition.background_images = definition
+            .backgrounds
+            .iter()
+            .map(|layer| load_context.load(layer.image.clone()))
+            .collect();
+        Ok(definition)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
@@ -0,0 +1,216 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::{
+        render_resource::{
+            AsBindGroup, Extent3d, ShaderRef, TextureDimension, TextureFormat, TextureUsages,
+        },
+        view::RenderLayers,
+    },
+    sprite::{AlphaMode2d, Material2d, Material2dPlugin},
+};
+use tiny_bail::prelude::*;
+
+use crate::{
+    GameSet,
+    assets::Levels,
+    game::{
+        level::{definition::LevelDefinition, terrain_to_world},
+        rendering::GameRenderLayers,
+        yup::Yup,
+    },
+    screens::Screen,
+};
+
+const SHADER_ASSET_PATH: &str = "shaders/darkness.wgsl";
+// How much of the terrain can still be made out where there's no light at all.
+const DARKNESS: f32 = 0.95;
+// Radii are in world units.
+const EXIT_LIGHT_RADIUS: f32 = 60.;
+const HATCH_LIGHT_RADIUS: f32 = 60.;
+// Above the terrain, which sits at 0, and below the Yups.
+const OVERLAY_Z: f32 = 0.5;
+const TORCH_LIGHT_RADIUS: f32 = 80.;
+const YUP_LIGHT_RADIUS: f32 = 40.;
+
+pub fn plugin(app: &mut App) {
+    app.add_plugins(Material2dPlugin::<DarknessMaterial>::default());
+    app.add_systems(OnEnter(Screen::InGame), init_darkness.in_set(GameSet::Init));
+    app.add_systems(OnExit(Screen::InGame), remove_darkness);
+    app.add_systems(
+        Update,
+        light_yups
+            .in_set(GameSet::Update)
+            .run_if(resource_exists::<Darkness>),
+    );
+}
+
+/// Present while the level being played is dark.
+#[derive(Resource, Debug)]
+struct Darkness {
+    /// A soft white circle, drawn into the light mask for every light.
+    light_image: Handle<Image>,
+}
+
+/// Given to Yups once they have a light of their own.
+#[derive(Component, Debug)]
+struct CarriesLight;
+
+/// Covers the level in darkness, except where the light mask says otherwise.
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+pub struct DarknessMaterial {
+    /// From 0 (none) to 1 (pitch black).
+    #[uniform(0)]
+    darkness: f32,
+    #[texture(1)]
+    #[sampler(2)]
+    light_mask: Handle<Image>,
+}
+
+impl Material2d for DarknessMaterial {
+    fn alpha_mode(&self) -> AlphaMode2d {
+        AlphaMode2d::Blend
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        SHADER_ASSET_PATH.into()
+    }
+}
+
+fn init_darkness(
+    mut commands: Commands,
+    definitions: Res<Assets<LevelDefinition>>,
+    mut images: ResMut<Assets<Image>>,
+    levels: Res<Levels>,
+    mut materials: ResMut<Assets<DarknessMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let definition = r!(definitions.get(&levels.first));
+    if !definition.dark {
+        return;
+    }
+    let terrain_size = r!(images.get(&definition.terrain_image)).size();
+
+    // The light mask covers the whole level, a pixel for each terrain pixel. Lights are drawn into
+    // it each frame by a camera of its own, in the same way as the terrain.
+    let mut light_mask = Image::new_fill(
+        Extent3d {
+            width: terrain_size.x,
+            height: terrain_size.y,
+            ..default()
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        // The camera rendering into this needs to know its size in the main world.
+        RenderAssetUsages::default(),
+    );
+    light_mask.texture_descriptor.usage =
+        TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING | TextureUsages::RENDER_ATTACHMENT;
+    let light_mask = images.add(light_mask);
+
+    commands.spawn((
+        Name::new("Light Camera"),
+        Camera2d,
+        Camera {
+            // Render this before the main camera, which uses the result.
+            order: -1,
+            target: light_mask.clone().into(),
+            // Anywhere without a light stays dark.
+            clear_color: ClearColorConfig::Custom(Color::BLACK),
+            ..default()
+        },
+        RenderLayers::layer(GameRenderLayers::Light.into()),
+        StateScoped(Screen::InGame),
+    ));
+
+    commands.spawn((
+        Name::new("Darkness"),
+        Mesh2d(meshes.add(Rectangle::from_size(terrain_size.as_vec2()))),
+        MeshMaterial2d(materials.add(DarknessMaterial {
+            darkness: DARKNESS,
+            light_mask,
+        })),
+        Transform::from_xyz(0., 0., OVERLAY_Z),
+        RenderLayers::layer(GameRenderLayers::Main.into()),
+        StateScoped(Screen::InGame),
+    ));
+
+    let light_image = images.add(light_image());
+    let lights = definition
+        .hatches
+        .iter()
+        .map(|p| (p, HATCH_LIGHT_RADIUS))
+        .chain(definition.exits.iter().map(|p| (p, EXIT_LIGHT_RADIUS)))
+        .chain(definition.torches.iter().map(|p| (p, TORCH_LIGHT_RADIUS)));
+    for (position, radius) in lights {
+        let position = terrain_to_world(terrain_size.as_vec2(), *position);
+        commands.spawn((
+            light(&light_image, radius),
+            Transform::from_translation(position.extend(0.)),
+            StateScoped(Screen::InGame),
+        ));
+    }
+
+    commands.insert_resource(Darkness { light_image });
+}
+
+fn remove_darkness(mut commands: Commands) {
+    commands.remove_resource::<Darkness>();
+}
+
+fn light(image: &Handle<Image>, radius: f32) -> impl Bundle {
+    (
+        Name::new("Light"),
+        Sprite {
+            custom_size: Some(Vec2::splat(radius * 2.)),
+            image: image.clone(),
+            ..default()
+        },
+        RenderLayers::layer(GameRenderLayers::Light.into()),
+    )
+}
+
+// White, fading from opaque in the middle to transparent at the edge. Overlapping lights add up
+// to brighter light.
+fn light_image() -> Image {
+    const SIZE: u32 = 64;
+    let half = SIZE as f32 / 2.;
+    let mut data = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let distance = Vec2::new(x as f32 + 0.5, y as f32 + 0.5).distance(Vec2::splat(half));
+            let falloff = (1. - distance / half).clamp(0., 1.);
+            // Smoothed, so the edge of the light isn't a hard ring.
+            let alpha = falloff * falloff * (3. - 2. * falloff);
+            data.extend_from_slice(&[255, 255, 255, (alpha * 255.) as u8]);
+        }
+    }
+    Image::new(
+        Extent3d {
+            width: SIZE,
+            height: SIZE,
+            ..default()
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    )
+}
+
+// Every Yup carries a light of their own, which goes wherever they do.
+fn light_yups(
+    mut commands: Commands,
+    darkness: Res<Darkness>,
+    yups: Query<Entity, (With<Yup>, Without<CarriesLight>)>,
+) {
+    for entity in &yups {
+        commands
+            .entity(entity)
+            .insert(CarriesLight)
+            .with_children(|p| {
+                p.spawn(light(&darkness.light_image, YUP_LIGHT_RADIUS));
+            });
+    }
+}
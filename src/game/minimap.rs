@@ -0,0 +1,267 @@
+use bevy::{prelude::*, ui::RelativeCursorPosition};
+use leafwing_input_manager::prelude::*;
+use tiny_bail::prelude::*;
+
+use crate::{
+    GameSet, MainCamera,
+    assets::Levels,
+    game::{
+        camera::CameraTarget,
+        level::{
+            self, Level, LevelRenderTargets, definition::LevelDefinition, terrain_to_world,
+            world_to_terrain,
+        },
+        yup::{CharacterState, Yup},
+    },
+    input::GameAction,
+    screens::{Screen, editor::EditingLevel},
+};
+
+const DOT_SIZE: f32 = 4.;
+const EXIT_MARKER_COLOR: Color = Color::srgb(0.95, 0.8, 0.2);
+const HATCH_MARKER_COLOR: Color = Color::srgb(0.3, 0.8, 0.3);
+const MARKER_SIZE: f32 = 8.;
+const MINIMAP_BACKGROUND_COLOR: Color = Color::srgba(0.1, 0.1, 0.15, 0.8);
+const MINIMAP_BORDER_COLOR: Color = Color::srgb(0.4, 0.4, 0.4);
+pub const MINIMAP_MARGIN: f32 = 10.;
+// The height follows from the aspect ratio of the level.
+pub const MINIMAP_WIDTH: f32 = 256.;
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(
+        OnEnter(Screen::InGame),
+        spawn_minimap.in_set(GameSet::Init).after(level::init),
+    );
+    app.add_systems(
+        Update,
+        (
+            update_minimap_image,
+            update_markers,
+            spawn_yup_dots,
+            update_yup_dots,
+            update_viewport_rect,
+            jump_camera,
+        )
+            .run_if(in_state(Screen::InGame).or(in_state(Screen::Editor))),
+    );
+}
+
+#[derive(Component, Debug)]
+pub struct Minimap;
+
+/// A hatch or exit on the minimap.
+#[derive(Component, Debug)]
+struct MinimapMarker;
+
+/// Marks the Yup a minimap dot represents.
+#[derive(Component, Debug)]
+struct MinimapDot(Entity);
+
+/// Outlines the part of the level visible through the main camera.
+#[derive(Component, Debug)]
+struct MinimapViewport;
+
+fn dot_color(state: &CharacterState) -> Color {
+    match state {
+        CharacterState::Blocking => Color::srgb(1.0, 0.5, 0.3),
+        CharacterState::Dead(_) => Color::srgb(0.8, 0.1, 0.1),
+        CharacterState::Falling => Color::srgb(0.5, 0.7, 1.0),
+        CharacterState::Walking => Color::srgb(0.4, 1.0, 0.4),
+    }
+}
+
+/// Places a node of the given size centred on a position normalised to the level size.
+fn centred_node(normalized: Vec2, size: f32) -> Node {
+    Node {
+        height: Val::Px(size),
+        left: Val::Percent(normalized.x * 100.),
+        margin: UiRect {
+            left: Val::Px(-size / 2.),
+            top: Val::Px(-size / 2.),
+            ..default()
+        },
+        position_type: PositionType::Absolute,
+        top: Val::Percent(normalized.y * 100.),
+        width: Val::Px(size),
+        ..default()
+    }
+}
+
+pub fn spawn_minimap(
+    mut commands: Commands,
+    level: Query<&Level>,
+    level_targets: Res<LevelRenderTargets>,
+    screen: Res<State<Screen>>,
+) {
+    let level = r!(level.get_single());
+
+    commands
+        .spawn((
+            Name::new("Minimap"),
+            Minimap,
+            BackgroundColor(MINIMAP_BACKGROUND_COLOR),
+            BorderColor(MINIMAP_BORDER_COLOR),
+            // This is the same image as the main view, so it's always up to date.
+            ImageNode::new(level_targets.source.clone()),
+            Node {
+                border: UiRect::all(Val::Px(2.)),
+                bottom: Val::Px(MINIMAP_MARGIN),
+                height: Val::Px(MINIMAP_WIDTH * level.size.y / level.size.x),
+                overflow: Overflow::clip(),
+                position_type: PositionType::Absolute,
+                right: Val::Px(MINIMAP_MARGIN),
+                width: Val::Px(MINIMAP_WIDTH),
+                ..default()
+            },
+            RelativeCursorPosition::default(),
+            StateScoped(screen.get().clone()),
+        ))
+        .with_children(|p| {
+            p.spawn((
+                Name::new("Minimap Viewport"),
+                MinimapViewport,
+                BorderColor(Color::WHITE),
+                Node {
+                    border: UiRect::all(Val::Px(1.)),
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+            ));
+        });
+}
+
+// The level render targets are swapped every frame, so follow along.
+fn update_minimap_image(
+    level_targets: Res<LevelRenderTargets>,
+    mut minimap: Query<&mut ImageNode, With<Minimap>>,
+) {
+    if !level_targets.is_changed() {
+        return;
+    }
+
+    for mut image in &mut minimap {
+        image.image = level_targets.source.clone();
+    }
+}
+
+// Hatches and exits come and go while editing, so markers follow whatever is being edited.
+fn update_markers(
+    mut commands: Commands,
+    definitions: Res<Assets<LevelDefinition>>,
+    editing: Option<Res<EditingLevel>>,
+    level: Query<&Level>,
+    levels: Res<Levels>,
+    markers: Query<Entity, With<MinimapMarker>>,
+    minimap: Query<(Entity, Ref<Minimap>)>,
+) {
+    let (entity, minimap) = rq!(minimap.get_single());
+    let editing_changed = editing.as_ref().is_some_and(|editing| editing.is_changed());
+    if !minimap.is_added() && !editing_changed {
+        return;
+    }
+    let level = r!(level.get_single());
+    let definition = match editing.as_deref() {
+        Some(editing) => &**editing,
+        None => r!(definitions.get(&levels.first)),
+    };
+
+    for marker in &markers {
+        commands.entity(marker).despawn_recursive();
+    }
+    commands.entity(entity).with_children(|p| {
+        let hatches = definition.hatches.iter().map(|h| (HATCH_MARKER_COLOR, h));
+        let exits = definition.exits.iter().map(|e| (EXIT_MARKER_COLOR, e));
+        for (color, pos) in hatches.chain(exits) {
+            p.spawn((
+                Name::new("Minimap Marker"),
+                MinimapMarker,
+                BackgroundColor(color),
+                centred_node(*pos / level.size, MARKER_SIZE),
+            ));
+        }
+    });
+}
+
+fn spawn_yup_dots(
+    mut commands: Commands,
+    minimap: Query<Entity, With<Minimap>>,
+    yups: Query<Entity, Added<Yup>>,
+) {
+    let minimap = rq!(minimap.get_single());
+    for yup in &yups {
+        commands.entity(minimap).with_children(|p| {
+            p.spawn((
+                Name::new("Minimap Dot"),
+                MinimapDot(yup),
+                BackgroundColor(Color::NONE),
+                centred_node(Vec2::ZERO, DOT_SIZE),
+            ));
+        });
+    }
+}
+
+fn update_yup_dots(
+    mut commands: Commands,
+    mut dots: Query<(Entity, &MinimapDot, &mut BackgroundColor, &mut Node)>,
+    level: Query<(&Level, &GlobalTransform)>,
+    yups: Query<(&CharacterState, &GlobalTransform), With<Yup>>,
+) {
+    let (level, level_transform) = rq!(level.get_single());
+    for (dot, MinimapDot(yup), mut background, mut node) in &mut dots {
+        let Ok((state, transform)) = yups.get(*yup) else {
+            // The Yup is no more.
+            commands.entity(dot).despawn_recursive();
+            continue;
+        };
+
+        let mesh_pos = level_transform
+            .affine()
+            .inverse()
+            .transform_point3(transform.translation());
+        let normalized = world_to_terrain(level.size, mesh_pos.truncate()) / level.size;
+        node.left = Val::Percent(normalized.x * 100.);
+        node.top = Val::Percent(normalized.y * 100.);
+        background.0 = dot_color(state);
+    }
+}
+
+fn update_viewport_rect(
+    camera: Single<(&GlobalTransform, &OrthographicProjection), With<MainCamera>>,
+    level: Query<&Level>,
+    mut viewport: Query<&mut Node, With<MinimapViewport>>,
+) {
+    let (cam_transform, projection) = *camera;
+    let level = rq!(level.get_single());
+    let mut node = rq!(viewport.get_single_mut());
+
+    let view_size = projection.area.size();
+    let top_left =
+        cam_transform.translation().truncate() + Vec2::new(-view_size.x, view_size.y) / 2.;
+    let normalized_pos = world_to_terrain(level.size, top_left) / level.size;
+    let normalized_size = view_size / level.size;
+
+    node.left = Val::Percent(normalized_pos.x * 100.);
+    node.top = Val::Percent(normalized_pos.y * 100.);
+    node.width = Val::Percent(normalized_size.x * 100.);
+    node.height = Val::Percent(normalized_size.y * 100.);
+}
+
+// Clicking, or dragging across, the minimap moves the camera to that point in the level.
+fn jump_camera(
+    action_state: Res<ActionState<GameAction>>,
+    level: Query<&Level>,
+    minimap: Query<&RelativeCursorPosition, With<Minimap>>,
+    mut target: ResMut<CameraTarget>,
+) {
+    if !action_state.pressed(&GameAction::Select) {
+        return;
+    }
+
+    let cursor = rq!(minimap.get_single());
+    if !cursor.mouse_over() {
+        return;
+    }
+    let level = rq!(level.get_single());
+    let normalized = r!(cursor.normalized);
+    **target = terrain_to_world(level.size, normalized * level.size);
+}
@@ -0,0 +1,315 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use tiny_bail::prelude::*;
+
+use crate::{
+    GameSet,
+    assets::Levels,
+    game::{
+        Game,
+        cursor::GameCursor,
+        level::{definition::LevelDefinition, terrain_to_world, world_to_terrain},
+        replay, skills,
+        yup::{Yup, feet},
+    },
+    screens::Screen,
+};
+
+// How far above an elevator a Yup's feet can be and still ride along, in world units.
+const ELEVATOR_RIDE_TOLERANCE: f32 = 3.;
+// In front of the terrain (and any darkness), behind hazards and Yups.
+const OBJECT_Z: f32 = 0.7;
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<SolidObjects>();
+    app.add_systems(OnEnter(Screen::InGame), spawn_objects.in_set(GameSet::Init));
+    app.add_systems(OnExit(Screen::InGame), clear_solid_objects);
+    app.add_systems(
+        Update,
+        pull_levers
+            .in_set(GameSet::RecordInput)
+            .after(replay::play_back)
+            .before(skills::assign_skill),
+    );
+    app.add_systems(Update, update_object_sprites.in_set(GameSet::Update));
+    app.add_systems(
+        FixedUpdate,
+        (press_plates, move_elevators, update_solid_objects)
+            .chain()
+            .run_if(in_state(Game::Playing)),
+    );
+}
+
+/// What a level object is, and what it does. Objects are either triggers, which act on another
+/// object by its id, or are acted on themselves, which makes them active.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum ObjectKind {
+    /// Solid when active, and open otherwise.
+    Bridge,
+    /// Open when active, and solid otherwise.
+    Door,
+    /// A solid platform which travels between its starting position and each waypoint in turn,
+    /// while active. Yups standing on it go along for the ride.
+    Elevator {
+        /// The top left corner at each stop, in terrain pixel coordinates.
+        waypoints: Vec<Vec2>,
+        /// In terrain pixels per second.
+        speed: f32,
+    },
+    /// Toggles the target each time the player selects it. Active when pulled.
+    Lever { target: String },
+    /// Toggles the target for as long as a Yup is standing on it. Active while pressed.
+    PressurePlate { target: String },
+}
+
+impl ObjectKind {
+    /// The id of the object acted on, for triggers.
+    pub fn target(&self) -> Option<&str> {
+        match self {
+            Self::Lever { target } | Self::PressurePlate { target } => Some(target),
+            Self::Bridge | Self::Door | Self::Elevator { .. } => None,
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            Self::Bridge => Color::srgb(0.55, 0.4, 0.25),
+            Self::Door => Color::srgb(0.4, 0.3, 0.2),
+            Self::Elevator { .. } => Color::srgb(0.5, 0.5, 0.55),
+            Self::Lever { .. } => Color::srgb(0.9, 0.75, 0.2),
+            Self::PressurePlate { .. } => Color::srgb(0.7, 0.3, 0.3),
+        }
+    }
+}
+
+/// A switch, door, bridge or elevator placed in the level.
+#[derive(Component, Debug)]
+pub struct LevelObject {
+    pub active: bool,
+    /// In world coordinates.
+    pub area: Rect,
+    /// How triggers refer to this object. Needn't be unique, in which case a trigger acts on every
+    /// object with the id.
+    pub id: String,
+    /// Pressure plates put their target back this way once they're released.
+    initially_active: bool,
+    pub kind: ObjectKind,
+}
+
+impl LevelObject {
+    /// Whether Yups collide with the object.
+    pub fn is_solid(&self) -> bool {
+        match self.kind {
+            ObjectKind::Bridge => self.active,
+            ObjectKind::Door => !self.active,
+            ObjectKind::Elevator { .. } => true,
+            ObjectKind::Lever { .. } | ObjectKind::PressurePlate { .. } => false,
+        }
+    }
+}
+
+/// Where an elevator is going.
+#[derive(Component, Debug)]
+struct ElevatorRoute {
+    /// Centres of each stop in world coordinates, starting with where the elevator started.
+    stops: Vec<Vec2>,
+    next: usize,
+}
+
+/// The areas, in world coordinates, that level objects currently make solid. These are written
+/// into the collision terrain every frame, see [`stamp_solid_objects`].
+#[derive(Resource, Debug, Default)]
+pub struct SolidObjects(pub Vec<Rect>);
+
+/// Makes the areas covered by solid objects solid in a copy of the terrain image.
+pub fn stamp_solid_objects(image: &mut Image, level_size: Vec2, solids: &SolidObjects) {
+    let width = image.width() as usize;
+    let size = image.size().as_vec2();
+    for area in &solids.0 {
+        // Terrain pixels have y pointing down, so the corners swap over.
+        let min = world_to_terrain(level_size, Vec2::new(area.min.x, area.max.y));
+        let max = world_to_terrain(level_size, Vec2::new(area.max.x, area.min.y));
+        let min = min.clamp(Vec2::ZERO, size).as_uvec2();
+        let max = max.clamp(Vec2::ZERO, size).as_uvec2();
+        for y in min.y..max.y {
+            for x in min.x..max.x {
+                // Only the alpha matters for collisions.
+                let alpha = (y as usize * width + x as usize) * 4 + 3;
+                *c!(image.data.get_mut(alpha)) = 255;
+            }
+        }
+    }
+}
+
+fn spawn_objects(
+    mut commands: Commands,
+    definitions: Res<Assets<LevelDefinition>>,
+    images: Res<Assets<Image>>,
+    levels: Res<Levels>,
+) {
+    let definition = r!(definitions.get(&levels.first));
+    let terrain_size = r!(images.get(&definition.terrain_image)).size_f32();
+    let to_world = |top_left: Vec2, size: Vec2| {
+        Rect::from_corners(
+            terrain_to_world(terrain_size, top_left),
+            terrain_to_world(terrain_size, top_left + size),
+        )
+    };
+
+    for object in &definition.objects {
+        let area = to_world(object.position, object.size);
+        let mut entity = commands.spawn((
+            Name::new(format!("Level Object {}", object.id)),
+            LevelObject {
+                active: object.active,
+                area,
+                id: object.id.clone(),
+                initially_active: object.active,
+                kind: object.kind.clone(),
+            },
+            Sprite::from_color(object.kind.color(), area.size()),
+            Transform::from_translation(area.center().extend(OBJECT_Z)),
+            StateScoped(Screen::InGame),
+        ));
+        if let ObjectKind::Elevator { waypoints, .. } = &object.kind {
+            let stops = std::iter::once(area.center())
+                .chain(waypoints.iter().map(|p| to_world(*p, object.size).center()))
+                .collect();
+            entity.insert(ElevatorRoute { stops, next: 1 });
+        }
+    }
+}
+
+fn clear_solid_objects(mut solids: ResMut<SolidObjects>) {
+    solids.0.clear();
+}
+
+// Each trigger that fires gives the id of its target, and either a state to put it in or `None`
+// to toggle it.
+fn apply_triggers(objects: &mut Query<&mut LevelObject>, triggered: Vec<(String, Option<bool>)>) {
+    for (target, state) in triggered {
+        for mut object in objects.iter_mut().filter(|o| o.id == target) {
+            object.active = state.unwrap_or(!object.active);
+        }
+    }
+}
+
+// A click that pulls a lever is used up, rather than also giving a skill to a Yup nearby.
+fn pull_levers(mut cursor: ResMut<GameCursor>, mut objects: Query<&mut LevelObject>) {
+    if !cursor.select_just_pressed {
+        return;
+    }
+    let position = rq!(cursor.world_position);
+
+    let mut triggered = vec![];
+    for mut lever in &mut objects {
+        let ObjectKind::Lever { target } = &lever.kind else {
+            continue;
+        };
+        if lever.area.contains(position) {
+            triggered.push((target.clone(), None));
+            lever.active = !lever.active;
+        }
+    }
+    if !triggered.is_empty() {
+        cursor.select_just_pressed = false;
+    }
+    apply_triggers(&mut objects, triggered);
+}
+
+fn press_plates(mut objects: Query<&mut LevelObject>, yups: Query<&Transform, With<Yup>>) {
+    let mut triggered = vec![];
+    for mut plate in &mut objects {
+        let ObjectKind::PressurePlate { target } = &plate.kind else {
+            continue;
+        };
+        let pressed = yups.iter().any(|t| plate.area.contains(feet(t)));
+        if pressed != plate.active {
+            triggered.push((target.clone(), pressed));
+            plate.active = pressed;
+        }
+    }
+
+    // A released plate puts its target back how it started.
+    let triggered = triggered
+        .into_iter()
+        .map(|(target, pressed)| {
+            let initially_active = objects
+                .iter()
+                .find(|o| o.id == target)
+                .is_some_and(|o| o.initially_active);
+            (target, Some(pressed != initially_active))
+        })
+        .collect();
+    apply_triggers(&mut objects, triggered);
+}
+
+fn move_elevators(
+    mut elevators: Query<(&mut LevelObject, &mut ElevatorRoute)>,
+    time: Res<Time>,
+    mut yups: Query<&mut Transform, With<Yup>>,
+) {
+    for (mut elevator, mut route) in &mut elevators {
+        let ObjectKind::Elevator { speed, .. } = elevator.kind else {
+            continue;
+        };
+        if !elevator.active || route.stops.len() < 2 {
+            continue;
+        }
+
+        let centre = elevator.area.center();
+        let stop = route.stops[route.next];
+        let max_step = speed * time.delta_secs();
+        let step = if centre.distance(stop) <= max_step {
+            // Land exactly on the stop, so that rounding doesn't leave the elevator just short.
+            route.next = (route.next + 1) % route.stops.len();
+            stop - centre
+        } else {
+            (stop - centre).clamp_length_max(max_step)
+        };
+
+        // Anyone standing on top rides along.
+        let top = elevator.area.max.y;
+        for mut transform in &mut yups {
+            let feet = feet(&transform);
+            let on_top = (elevator.area.min.x..=elevator.area.max.x).contains(&feet.x)
+                && (top - ELEVATOR_RIDE_TOLERANCE..=top + ELEVATOR_RIDE_TOLERANCE)
+                    .contains(&feet.y);
+            if on_top {
+                transform.translation += step.extend(0.);
+            }
+        }
+
+        elevator.area = Rect::from_center_size(centre + step, elevator.area.size());
+    }
+}
+
+fn update_solid_objects(objects: Query<&LevelObject>, mut solids: ResMut<SolidObjects>) {
+    solids.0 = objects
+        .iter()
+        .filter(|o| o.is_solid())
+        .map(|o| o.area)
+        .collect();
+}
+
+fn update_object_sprites(
+    mut objects: Query<(&LevelObject, &mut Sprite, &mut Transform), Changed<LevelObject>>,
+) {
+    for (object, mut sprite, mut transform) in &mut objects {
+        transform.translation = object.area.center().extend(OBJECT_Z);
+        // Open doors and retracted bridges are still shown, faintly, so the player knows they're
+        // there.
+        let alpha = match object.kind {
+            ObjectKind::Bridge | ObjectKind::Door if !object.is_solid() => 0.25,
+            _ => 1.,
+        };
+        // Switched on triggers are lit up.
+        let color = match object.kind {
+            ObjectKind::Lever { .. } | ObjectKind::PressurePlate { .. } if object.active => {
+                object.kind.color().lighter(0.2)
+            }
+            _ => object.kind.color(),
+        };
+        sprite.color = color.with_alpha(alpha);
+    }
+}
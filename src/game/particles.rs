@@ -0,0 +1,293 @@
+use bevy::{prelude::*, render::view::RenderLayers};
+use tiny_bail::prelude::*;
+
+use crate::{
+    GameSet,
+    assets::Levels,
+    game::{
+        hazards::YupDied,
+        level::{TerrainErased, definition::LevelDefinition, terrain_to_world, world_to_terrain},
+        rendering::GameRenderLayers,
+        yup::Death,
+    },
+    screens::Screen,
+};
+
+// How many debris particles a single frame of digging throws up, at most.
+const DEBRIS_PER_ERASE: usize = 6;
+const DEBRIS_SECS: f32 = 0.8;
+const EXPLOSION_PARTICLES: usize = 40;
+const EXPLOSION_SECS: f32 = 0.6;
+// In world units per second squared.
+const GRAVITY: f32 = 400.;
+// No more than this many particles start each frame, however much is going on, so that a hundred
+// Yups all doing something at once doesn't cost a hundred times as much.
+const MAX_NEW_PER_FRAME: usize = 64;
+// Particles are spawned once, up front, and reused.
+const POOL_SIZE: usize = 512;
+const PARTICLE_SIZE: f32 = 2.;
+// In front of the Yups.
+const PARTICLE_Z: f32 = 2.;
+const SPARKLE_PARTICLES: usize = 12;
+const SPARKLE_SECS: f32 = 1.;
+
+pub fn plugin(app: &mut App) {
+    app.add_event::<SpawnParticles>();
+    app.init_resource::<ErodedTerrain>();
+    app.add_systems(
+        OnEnter(Screen::InGame),
+        (reset_eroded_terrain, spawn_particle_pool).in_set(GameSet::Init),
+    );
+    app.add_systems(
+        Update,
+        (burn_up, emit_particles, update_particles)
+            .chain()
+            .in_set(GameSet::Update),
+    );
+}
+
+/// Effects that aren't tied to the terrain. Digging makes its own debris, see [`TerrainErased`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ParticleEffect {
+    /// A Yup has fallen into lava.
+    Explosion,
+    /// A Yup has made it home.
+    Sparkle,
+}
+
+/// Send this to start a particle effect at a position in the world.
+#[derive(Event, Debug)]
+pub struct SpawnParticles {
+    pub effect: ParticleEffect,
+    pub position: Vec2,
+}
+
+/// A particle from the pool. Idle particles are hidden, with no time remaining.
+#[derive(Component, Debug, Default)]
+struct Particle {
+    color: Color,
+    /// Whether the particle falls.
+    gravity: bool,
+    lifetime_secs: f32,
+    remaining_secs: f32,
+    velocity: Vec2,
+}
+
+/// The terrain pixels that have already been dug away. Terrain is only ever edited on the GPU, so
+/// this is kept alongside it so that digging the same hole twice doesn't throw up debris from thin
+/// air.
+#[derive(Resource, Debug, Default)]
+struct ErodedTerrain {
+    pixels: Vec<bool>,
+    size: UVec2,
+}
+
+/// A tiny xorshift generator. Particles only need to look random, not be random.
+#[derive(Debug)]
+struct ParticleRng(u32);
+
+impl Default for ParticleRng {
+    fn default() -> Self {
+        Self(0x9e37_79b9)
+    }
+}
+
+impl ParticleRng {
+    /// A number from 0 up to (but not including) 1.
+    fn next(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        (self.0 >> 8) as f32 / (1 << 24) as f32
+    }
+
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next()
+    }
+
+    /// A vector pointing in a random direction, with a length between `min` and `max`.
+    fn direction(&mut self, min: f32, max: f32) -> Vec2 {
+        Vec2::from_angle(self.range(0., std::f32::consts::TAU)) * self.range(min, max)
+    }
+}
+
+fn reset_eroded_terrain(
+    definitions: Res<Assets<LevelDefinition>>,
+    mut eroded: ResMut<ErodedTerrain>,
+    images: Res<Assets<Image>>,
+    levels: Res<Levels>,
+) {
+    let definition = r!(definitions.get(&levels.first));
+    let terrain = r!(images.get(&definition.terrain_image));
+    eroded.size = terrain.size();
+    eroded.pixels = vec![false; (eroded.size.x * eroded.size.y) as usize];
+}
+
+fn spawn_particle_pool(mut commands: Commands) {
+    for _ in 0..POOL_SIZE {
+        commands.spawn((
+            Name::new("Particle"),
+            Particle::default(),
+            Sprite::from_color(Color::WHITE, Vec2::splat(PARTICLE_SIZE)),
+            Transform::from_xyz(0., 0., PARTICLE_Z),
+            RenderLayers::layer(GameRenderLayers::Main.into()),
+            StateScoped(Screen::InGame),
+            Visibility::Hidden,
+        ));
+    }
+}
+
+fn burn_up(mut deaths: EventReader<YupDied>, mut particles: EventWriter<SpawnParticles>) {
+    for YupDied { death, position } in deaths.read() {
+        if *death == Death::Burned {
+            particles.send(SpawnParticles {
+                effect: ParticleEffect::Explosion,
+                position: *position,
+            });
+        }
+    }
+}
+
+fn emit_particles(
+    definitions: Res<Assets<LevelDefinition>>,
+    mut effects: EventReader<SpawnParticles>,
+    mut eroded: ResMut<ErodedTerrain>,
+    mut erased: EventReader<TerrainErased>,
+    images: Res<Assets<Image>>,
+    levels: Res<Levels>,
+    mut particles: Query<(&mut Particle, &mut Transform, &mut Visibility)>,
+    mut rng: Local<ParticleRng>,
+) {
+    // Everything that wants to start this frame, as position and particle.
+    let mut new = vec![];
+
+    let terrain = definitions
+        .get(&levels.first)
+        .and_then(|definition| images.get(&definition.terrain_image));
+    for TerrainErased { position, radius } in erased.read() {
+        let terrain = c!(terrain);
+        new.extend(
+            erode(terrain, &mut eroded, *position, *radius)
+                .into_iter()
+                .map(|(pixel_position, color)| {
+                    (pixel_position, Particle {
+                        color,
+                        gravity: true,
+                        lifetime_secs: DEBRIS_SECS,
+                        // Mostly upwards, out of the hole.
+                        velocity: rng.direction(20., 80.) + Vec2::Y * 60.,
+                        ..default()
+                    })
+                }),
+        );
+    }
+
+    for SpawnParticles { effect, position } in effects.read() {
+        match effect {
+            ParticleEffect::Explosion => {
+                for _ in 0..EXPLOSION_PARTICLES {
+                    let heat = rng.next();
+                    new.push((*position, Particle {
+                        color: Color::srgb(1., 0.4 + 0.5 * heat, 0.1 * heat),
+                        gravity: true,
+                        lifetime_secs: EXPLOSION_SECS * rng.range(0.5, 1.),
+                        velocity: rng.direction(60., 200.),
+                        ..default()
+                    }));
+                }
+            }
+            ParticleEffect::Sparkle => {
+                for _ in 0..SPARKLE_PARTICLES {
+                    new.push((*position + rng.direction(0., 8.), Particle {
+                        color: Color::srgb(1., 0.95, 0.6),
+                        gravity: false,
+                        lifetime_secs: SPARKLE_SECS * rng.range(0.5, 1.),
+                        velocity: Vec2::new(rng.range(-10., 10.), rng.range(20., 50.)),
+                        ..default()
+                    }));
+                }
+            }
+        }
+    }
+
+    let idle = particles
+        .iter_mut()
+        .filter(|(particle, _, _)| particle.remaining_secs <= 0.);
+    for ((position, new), (mut particle, mut transform, mut visibility)) in
+        new.into_iter().take(MAX_NEW_PER_FRAME).zip(idle)
+    {
+        *particle = Particle {
+            remaining_secs: new.lifetime_secs,
+            ..new
+        };
+        transform.translation = position.extend(PARTICLE_Z);
+        *visibility = Visibility::Inherited;
+    }
+}
+
+// Marks the terrain within the radius as dug, returning the world position and colour of a few of
+// the pixels that were still there.
+fn erode(
+    terrain: &Image,
+    eroded: &mut ErodedTerrain,
+    position: Vec2,
+    radius: f32,
+) -> Vec<(Vec2, Color)> {
+    let size = eroded.size.as_vec2();
+    let centre = world_to_terrain(size, position);
+    let min = (centre - radius).max(Vec2::ZERO).as_uvec2();
+    let max = (centre + radius).min(size - 1.).as_uvec2();
+
+    let mut removed = vec![];
+    for y in min.y..=max.y {
+        for x in min.x..=max.x {
+            if UVec2::new(x, y).as_vec2().distance(centre) > radius {
+                continue;
+            }
+            let index = (y * eroded.size.x + x) as usize;
+            if eroded.pixels.get(index) != Some(&false) {
+                continue;
+            }
+            eroded.pixels[index] = true;
+
+            let color = cq!(terrain.get_color_at(x, y));
+            if color.alpha() > 0.5 {
+                removed.push((UVec2::new(x, y), color));
+            }
+        }
+    }
+
+    // Spread evenly across the hole, rather than all from one corner.
+    let step = removed.len().div_ceil(DEBRIS_PER_ERASE).max(1);
+    removed
+        .into_iter()
+        .step_by(step)
+        .map(|(pixel, color)| (terrain_to_world(size, pixel.as_vec2()), color))
+        .collect()
+}
+
+fn update_particles(
+    mut particles: Query<(&mut Particle, &mut Sprite, &mut Transform, &mut Visibility)>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_secs();
+    for (mut particle, mut sprite, mut transform, mut visibility) in &mut particles {
+        if particle.remaining_secs <= 0. {
+            continue;
+        }
+
+        particle.remaining_secs -= dt;
+        if particle.remaining_secs <= 0. {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+
+        if particle.gravity {
+            particle.velocity.y -= GRAVITY * dt;
+        }
+        transform.translation += (particle.velocity * dt).extend(0.);
+        // Fade out over the particle's life.
+        let life = particle.remaining_secs / particle.lifetime_secs;
+        sprite.color = particle.color.with_alpha(particle.color.alpha() * life);
+    }
+}
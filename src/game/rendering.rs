@@ -1,6 +1,11 @@
 pub enum GameRenderLayers {
     Main = 0,
     Terrain = 1,
+    /// Parallax layers, seen by the main camera behind the terrain but kept out of the terrain
+    /// render target so that Yups don't collide with them.
+    Background = 2,
+    /// Lights for dark levels, rendered into a mask of their own. See `lighting`.
+    Light = 3,
 }
 
 impl Into<usize> for GameRenderLayers {
@@ -8,6 +13,8 @@ impl Into<usize> for GameRenderLayers {
         match self {
             GameRenderLayers::Main => 0,
             GameRenderLayers::Terrain => 1,
+            GameRenderLayers::Background => 2,
+            GameRenderLayers::Light => 3,
         }
     }
 }
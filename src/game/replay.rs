@@ -0,0 +1,248 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    GameSet,
+    game::{
+        Game,
+        cursor::{self, GameCursor},
+        hatch::{self, ReleaseRate, ReleaseRateChanged},
+        skills::{SelectedSkill, Skill},
+    },
+    screens::Screen,
+};
+
+/// Replays are kept next to their level, with this in place of the level's extension.
+pub const REPLAY_EXTENSION: &str = ".replay.ron";
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<Recording>();
+    app.add_systems(
+        OnEnter(Screen::InGame),
+        start_recording.in_set(GameSet::Init),
+    );
+    #[cfg(all(feature = "dev", not(target_arch = "wasm32")))]
+    app.add_systems(
+        OnExit(Screen::InGame),
+        save_recording.before(remove_playback),
+    );
+    app.add_systems(OnExit(Screen::InGame), remove_playback);
+    app.add_systems(FixedUpdate, count_tick.run_if(in_state(Game::Playing)));
+    app.add_systems(
+        Update,
+        (
+            tick_recording.in_set(GameSet::TickTimers),
+            play_back
+                .in_set(GameSet::RecordInput)
+                .after(cursor::update_world_position)
+                .run_if(resource_exists::<Playback>),
+            // Everything the player can do has been done by now.
+            record_input
+                .in_set(GameSet::Update)
+                .after(hatch::apply_release_rate),
+        ),
+    );
+}
+
+/// Everything the player did during a level, so that it can be played back later.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Replay {
+    /// How long the level was played for, in game time.
+    pub duration_secs: f32,
+    /// In the order they happened.
+    pub inputs: Vec<TimedInput>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TimedInput {
+    /// How many fixed timesteps the level had run when it happened, which leaves out any time
+    /// spent paused or in dialogue. Counting ticks rather than seconds means an input lands on
+    /// the same tick, with the Yups in the same places, however the frames fell.
+    pub tick: u32,
+    pub input: ReplayInput,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum ReplayInput {
+    /// Where the player is pointing and whether they're selecting. Only recorded while they're
+    /// selecting, and as they let go, since that's all that makes a difference to the level.
+    Cursor {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        position: Option<Vec2>,
+        select: bool,
+    },
+    SelectSkill(Skill),
+    /// The hatches' new release rate.
+    ReleaseRate(u32),
+}
+
+/// The level being played so far. Starts afresh with each level.
+#[derive(Resource, Debug, Default)]
+pub struct Recording {
+    pub replay: Replay,
+    // The last cursor input and skill recorded, so that only changes are recorded.
+    last_cursor: Option<ReplayInput>,
+    last_skill: Option<Skill>,
+    // Fixed timesteps run so far.
+    ticks: u32,
+}
+
+impl Recording {
+    /// Game time since the level started.
+    pub fn elapsed_secs(&self) -> f32 {
+        self.replay.duration_secs
+    }
+
+    fn record(&mut self, input: ReplayInput) {
+        self.replay.inputs.push(TimedInput {
+            tick: self.ticks,
+            input,
+        });
+    }
+}
+
+/// Plays back a replay in place of the player's own input. Insert this before the level starts,
+/// and it's removed once the level is over.
+#[derive(Resource, Debug)]
+pub struct Playback {
+    pub replay: Replay,
+    // The next of the replay's inputs to play back.
+    next: usize,
+    position: Option<Vec2>,
+    select: bool,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            next: 0,
+            position: None,
+            select: false,
+        }
+    }
+}
+
+/// The asset path of the replay for the level at the given asset path.
+pub fn replay_path(level_path: &str) -> String {
+    let stem = level_path.strip_suffix(".level.ron").unwrap_or(level_path);
+    format!("{stem}{REPLAY_EXTENSION}")
+}
+
+fn start_recording(mut recording: ResMut<Recording>) {
+    *recording = Recording::default();
+}
+
+fn remove_playback(mut commands: Commands) {
+    commands.remove_resource::<Playback>();
+}
+
+fn count_tick(mut recording: ResMut<Recording>) {
+    recording.ticks += 1;
+}
+
+fn tick_recording(mut recording: ResMut<Recording>, time: Res<Time>) {
+    recording.replay.duration_secs += time.delta_secs();
+}
+
+// Takes the place of the mouse, virtual cursor or touch, before anything acts on the cursor. Each
+// input is played back once the level has run as many ticks as when it was recorded, so that it
+// acts on the next tick, as it did then.
+pub fn play_back(
+    mut cursor: ResMut<GameCursor>,
+    mut playback: ResMut<Playback>,
+    mut rate: ResMut<ReleaseRate>,
+    recording: Res<Recording>,
+    mut selected: ResMut<SelectedSkill>,
+) {
+    let was_selecting = playback.select;
+    while let Some(timed) = playback.replay.inputs.get(playback.next).cloned() {
+        if timed.tick > recording.ticks {
+            break;
+        }
+        match timed.input {
+            // A press and a release can fall between the same two ticks. Leave the release until
+            // the next frame, so that the press isn't lost.
+            ReplayInput::Cursor { select, .. }
+                if select != playback.select && select == was_selecting =>
+            {
+                break;
+            }
+            ReplayInput::Cursor { position, select } => {
+                playback.position = position;
+                playback.select = select;
+            }
+            ReplayInput::SelectSkill(skill) if **selected != skill => **selected = skill,
+            ReplayInput::SelectSkill(_) => {}
+            ReplayInput::ReleaseRate(current) if rate.current != current => rate.current = current,
+            ReplayInput::ReleaseRate(_) => {}
+        }
+        playback.next += 1;
+    }
+
+    cursor.world_position = playback.position;
+    cursor.select_pressed = playback.select;
+    cursor.select_just_pressed = playback.select && !was_selecting;
+}
+
+fn record_input(
+    cursor: Res<GameCursor>,
+    mut rate_changes: EventReader<ReleaseRateChanged>,
+    mut recording: ResMut<Recording>,
+    selected: Res<SelectedSkill>,
+) {
+    for change in rate_changes.read() {
+        recording.record(ReplayInput::ReleaseRate(change.rate));
+    }
+    if recording.last_skill != Some(**selected) {
+        recording.last_skill = Some(**selected);
+        recording.record(ReplayInput::SelectSkill(**selected));
+    }
+
+    let was_selecting = matches!(
+        recording.last_cursor,
+        Some(ReplayInput::Cursor { select: true, .. })
+    );
+    if !cursor.select_pressed && !was_selecting {
+        return;
+    }
+    let input = ReplayInput::Cursor {
+        position: cursor.world_position,
+        select: cursor.select_pressed,
+    };
+    if recording.last_cursor.as_ref() != Some(&input) {
+        recording.last_cursor = Some(input.clone());
+        recording.record(input);
+    }
+}
+
+// Designers record a level's reference replay by rescuing enough Yups in a dev build. Replays
+// being played back are never saved over.
+#[cfg(all(feature = "dev", not(target_arch = "wasm32")))]
+fn save_recording(
+    asset_server: Res<AssetServer>,
+    levels: Res<crate::assets::Levels>,
+    playback: Option<Res<Playback>>,
+    recording: Res<Recording>,
+    stats: Res<crate::game::stats::LevelStats>,
+) {
+    use std::path::Path;
+
+    use tiny_bail::prelude::*;
+
+    const ASSETS_DIR: &str = "assets";
+
+    if playback.is_some() || stats.rescued < stats.rescue_target {
+        return;
+    }
+    let level_path = r!(asset_server.get_path(levels.first.id()));
+    let path = Path::new(ASSETS_DIR).join(replay_path(&level_path.path().to_string_lossy()));
+
+    match ron::ser::to_string_pretty(&recording.replay, ron::ser::PrettyConfig::default()) {
+        Ok(ron) => match std::fs::write(&path, ron) {
+            Ok(()) => info!("Saved replay to {}", path.display()),
+            Err(e) => error!("Could not save {}: {e}", path.display()),
+        },
+        Err(e) => error!("Could not serialize replay: {e}"),
+    }
+}
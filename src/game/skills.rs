@@ -0,0 +1,206 @@
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+use tiny_bail::prelude::*;
+
+use crate::{
+    GameSet,
+    assets::Levels,
+    audio::{PlaySfx, Sfx},
+    game::{
+        cursor::GameCursor,
+        level::definition::LevelDefinition,
+        replay,
+        yup::{CharacterState, Swimmer, Yup},
+    },
+    input::GameAction,
+    screens::Screen,
+};
+
+/// How close (in world units) the cursor needs to be to a Yup to give them a skill.
+const ASSIGN_DISTANCE: f32 = 20.;
+/// Room kept for the skill text in the bottom-left corner, wide enough for the longest label.
+pub const SKILL_TEXT_WIDTH: f32 = 200.;
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<SelectedSkill>();
+    app.init_resource::<SkillsLeft>();
+    app.add_systems(
+        OnEnter(Screen::InGame),
+        (reset_selected_skill, reset_skills_left, spawn_skill_text).in_set(GameSet::Init),
+    );
+    app.add_systems(
+        Update,
+        (cycle_skill, assign_skill, update_skill_text)
+            .chain()
+            .in_set(GameSet::RecordInput)
+            .after(replay::play_back),
+    );
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum Skill {
+    /// Stops a walking Yup in their tracks, and turns back any walkers who run into them.
+    Block,
+    /// Digs away the terrain under the cursor. This is the player's own skill, rather than one
+    /// given to a Yup.
+    #[default]
+    Dig,
+    /// Lets a Yup cross water without drowning, for the rest of the level.
+    Swim,
+}
+
+impl Skill {
+    /// In the order they're cycled through.
+    pub const ALL: [Self; 3] = [Self::Dig, Self::Block, Self::Swim];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Block => "Block",
+            Self::Dig => "Dig",
+            Self::Swim => "Swim",
+        }
+    }
+}
+
+/// The skill that selecting will use.
+#[derive(Resource, Debug, Default, Deref, DerefMut)]
+pub struct SelectedSkill(pub Skill);
+
+/// How many more times each skill can be given out this level. Skills that aren't here never run
+/// out.
+#[derive(Resource, Debug, Default)]
+pub struct SkillsLeft(pub Vec<(Skill, u32)>);
+
+impl SkillsLeft {
+    /// `None` if the skill never runs out.
+    pub fn get(&self, skill: Skill) -> Option<u32> {
+        self.0
+            .iter()
+            .find(|(s, _)| *s == skill)
+            .map(|(_, left)| *left)
+    }
+
+    fn use_one(&mut self, skill: Skill) {
+        if let Some((_, left)) = self.0.iter_mut().find(|(s, _)| *s == skill) {
+            *left = left.saturating_sub(1);
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+struct SkillText;
+
+fn reset_selected_skill(mut selected: ResMut<SelectedSkill>) {
+    *selected = SelectedSkill::default();
+}
+
+fn reset_skills_left(
+    definitions: Res<Assets<LevelDefinition>>,
+    levels: Res<Levels>,
+    mut skills_left: ResMut<SkillsLeft>,
+) {
+    let definition = r!(definitions.get(&levels.first));
+    skills_left.0 = Skill::ALL
+        .into_iter()
+        .filter_map(|skill| Some((skill, definition.skill_count(skill)?)))
+        .collect();
+}
+
+fn spawn_skill_text(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Skill Text"),
+        SkillText,
+        Node {
+            bottom: Val::Px(10.),
+            left: Val::Px(10.),
+            position_type: PositionType::Absolute,
+            width: Val::Px(SKILL_TEXT_WIDTH),
+            ..default()
+        },
+        StateScoped(Screen::InGame),
+        Text::default(),
+    ));
+}
+
+impl SelectedSkill {
+    /// Moves through [`Skill::ALL`] by the given number of steps, wrapping around at either end.
+    pub fn cycle(&mut self, step: isize) {
+        let count = Skill::ALL.len() as isize;
+        let current = Skill::ALL.iter().position(|s| *s == self.0).unwrap_or(0) as isize;
+        self.0 = Skill::ALL[(current + step).rem_euclid(count) as usize];
+    }
+}
+
+fn cycle_skill(action_state: Res<ActionState<GameAction>>, mut selected: ResMut<SelectedSkill>) {
+    if action_state.just_pressed(&GameAction::NextSkill) {
+        selected.cycle(1);
+    } else if action_state.just_pressed(&GameAction::PreviousSkill) {
+        selected.cycle(-1);
+    }
+}
+
+/// Of the given Yups, the one nearest a position in the world that's close enough to be given a
+/// skill from there, if any.
+pub fn nearest_in_reach<T>(
+    yups: impl IntoIterator<Item = T>,
+    position: Vec2,
+    transform: impl Fn(&T) -> &GlobalTransform,
+) -> Option<T> {
+    yups.into_iter()
+        .map(|yup| {
+            (
+                transform(&yup).translation().truncate().distance(position),
+                yup,
+            )
+        })
+        .filter(|(distance, _)| *distance < ASSIGN_DISTANCE)
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, yup)| yup)
+}
+
+// Digging is handled by `level::update_cursor_position`, since it acts on the terrain rather than
+// on a Yup.
+pub fn assign_skill(
+    mut commands: Commands,
+    cursor: Res<GameCursor>,
+    selected: Res<SelectedSkill>,
+    mut sfx: EventWriter<PlaySfx>,
+    mut skills_left: ResMut<SkillsLeft>,
+    mut yups: Query<(Entity, &GlobalTransform, &mut CharacterState, Has<Swimmer>), With<Yup>>,
+) {
+    if !cursor.select_just_pressed || skills_left.get(**selected) == Some(0) {
+        return;
+    }
+    let pos = rq!(cursor.world_position);
+
+    let (entity, transform, mut state, swimmer) =
+        rq!(nearest_in_reach(yups.iter_mut(), pos, |(_, t, _, _)| *t));
+
+    match **selected {
+        // Only a Yup with their feet on the ground can hold the line.
+        Skill::Block if *state == CharacterState::Walking => {
+            *state = CharacterState::Blocking;
+        }
+        Skill::Swim if !swimmer && !matches!(*state, CharacterState::Dead(_)) => {
+            commands.entity(entity).insert(Swimmer);
+        }
+        Skill::Block | Skill::Dig | Skill::Swim => return,
+    }
+    skills_left.use_one(**selected);
+    sfx.send(PlaySfx::new(Sfx::SkillAssigned).at(transform.translation().truncate()));
+}
+
+fn update_skill_text(
+    selected: Res<SelectedSkill>,
+    skills_left: Res<SkillsLeft>,
+    mut text: Single<&mut Text, With<SkillText>>,
+) {
+    if !selected.is_changed() && !skills_left.is_changed() && !text.is_added() {
+        return;
+    }
+    text.0 = match skills_left.get(**selected) {
+        Some(left) => format!("Skill: {} ({left})", selected.label()),
+        None => format!("Skill: {}", selected.label()),
+    };
+}
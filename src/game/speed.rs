@@ -0,0 +1,58 @@
+use bevy::prelude::*;
+use leafwing_input_manager::common_conditions::action_just_pressed;
+
+use crate::{GameSet, input::GameAction, screens::Screen};
+
+const FAST_FORWARD_SPEED: f32 = 3.;
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<GameSpeed>();
+    app.add_systems(OnExit(Screen::InGame), reset_game_speed);
+    app.add_systems(
+        Update,
+        toggle_fast_forward
+            .in_set(GameSet::RecordInput)
+            .run_if(action_just_pressed(GameAction::FastForward)),
+    );
+    app.add_systems(
+        Update,
+        apply_game_speed.run_if(resource_changed::<GameSpeed>),
+    );
+}
+
+/// How quickly time passes in the level. Everything driven by virtual time, including the fixed
+/// timestep, speeds up along with it.
+#[derive(Resource, Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum GameSpeed {
+    #[default]
+    Normal,
+    FastForward,
+}
+
+impl GameSpeed {
+    pub fn toggle(&mut self) {
+        *self = match self {
+            Self::Normal => Self::FastForward,
+            Self::FastForward => Self::Normal,
+        };
+    }
+
+    pub fn relative_speed(&self) -> f32 {
+        match self {
+            Self::Normal => 1.,
+            Self::FastForward => FAST_FORWARD_SPEED,
+        }
+    }
+}
+
+fn reset_game_speed(mut speed: ResMut<GameSpeed>) {
+    speed.set_if_neq(GameSpeed::Normal);
+}
+
+fn toggle_fast_forward(mut speed: ResMut<GameSpeed>) {
+    speed.toggle();
+}
+
+fn apply_game_speed(speed: Res<GameSpeed>, mut time: ResMut<Time<Virtual>>) {
+    time.set_relative_speed(speed.relative_speed());
+}
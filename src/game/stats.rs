@@ -0,0 +1,147 @@
+use bevy::prelude::*;
+use tiny_bail::prelude::*;
+
+use crate::{
+    GameSet,
+    assets::Levels,
+    game::{
+        cursor::GameCursor,
+        exits::YupRescued,
+        hazards::YupDied,
+        level::definition::LevelDefinition,
+        skills::nearest_in_reach,
+        yup::{CharacterState, Swimmer, Yup},
+    },
+    screens::Screen,
+};
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<LevelStats>();
+    app.add_systems(
+        OnEnter(Screen::InGame),
+        (reset_level_stats, spawn_stats_text).in_set(GameSet::Init),
+    );
+    app.add_systems(
+        Update,
+        (count_yups, update_stats_text)
+            .chain()
+            .in_set(GameSet::Update),
+    );
+}
+
+/// How the level is going: how many Yups have come out of the hatches, and what's become of them.
+#[derive(Resource, Debug, Default)]
+pub struct LevelStats {
+    pub died: u32,
+    pub released: u32,
+    pub rescue_target: u32,
+    pub rescued: u32,
+    /// All the Yups the level will release, from the hatches and from any triggers that have
+    /// fired so far.
+    pub total: u32,
+}
+
+impl LevelStats {
+    /// Yups still making their way through the level.
+    pub fn out(&self) -> u32 {
+        self.released.saturating_sub(self.rescued + self.died)
+    }
+
+    /// Whether every Yup has come out of the hatches and then either made it home or died.
+    pub fn all_accounted_for(&self) -> bool {
+        self.released >= self.total && self.out() == 0
+    }
+
+    /// How many of all the level's Yups have been rescued, as a percentage.
+    pub fn rescued_percent(&self) -> u32 {
+        percent(self.rescued, self.total)
+    }
+
+    /// How many of all the level's Yups need rescuing, as a percentage.
+    pub fn target_percent(&self) -> u32 {
+        percent(self.rescue_target, self.total)
+    }
+}
+
+fn percent(count: u32, total: u32) -> u32 {
+    (count * 100).checked_div(total).unwrap_or(0)
+}
+
+#[derive(Component, Debug)]
+struct StatsText;
+
+fn reset_level_stats(
+    definitions: Res<Assets<LevelDefinition>>,
+    levels: Res<Levels>,
+    mut stats: ResMut<LevelStats>,
+) {
+    let definition = r!(definitions.get(&levels.first));
+    *stats = LevelStats {
+        rescue_target: definition.rescue_target,
+        total: definition.yups,
+        ..default()
+    };
+}
+
+fn spawn_stats_text(mut commands: Commands) {
+    commands
+        .spawn((
+            Name::new("Stats"),
+            // Top left, beside the timer in the middle and the release controls on the right.
+            Node {
+                left: Val::Px(10.),
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.),
+                ..default()
+            },
+            StateScoped(Screen::InGame),
+        ))
+        .with_children(|p| {
+            p.spawn((Name::new("Stats Text"), StatsText, Text::default()));
+        });
+}
+
+fn count_yups(
+    mut deaths: EventReader<YupDied>,
+    released: Query<(), Added<Yup>>,
+    mut rescues: EventReader<YupRescued>,
+    mut stats: ResMut<LevelStats>,
+) {
+    let released = released.iter().count() as u32;
+    let died = deaths.read().count() as u32;
+    let rescued = rescues.read().count() as u32;
+    if released + died + rescued == 0 {
+        return;
+    }
+    stats.released += released;
+    stats.died += died;
+    stats.rescued += rescued;
+}
+
+fn update_stats_text(
+    cursor: Res<GameCursor>,
+    stats: Res<LevelStats>,
+    mut texts: Query<&mut Text, With<StatsText>>,
+    yups: Query<(&CharacterState, &GlobalTransform, Has<Swimmer>), With<Yup>>,
+) {
+    // The Yup that selecting would give a skill to, if any.
+    let hovered = cursor
+        .world_position
+        .and_then(|pos| nearest_in_reach(&yups, pos, |(_, t, _)| *t));
+    let hovered = match hovered {
+        Some((state, _, true)) => format!("{} (Swimmer)  ", state.label()),
+        Some((state, _, false)) => format!("{}  ", state.label()),
+        None => String::new(),
+    };
+
+    for mut text in &mut texts {
+        text.0 = format!(
+            "{hovered}Released {}/{}  Out {}  In {}% (need {}%)",
+            stats.released,
+            stats.total,
+            stats.out(),
+            stats.rescued_percent(),
+            stats.target_percent(),
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use bevy::prelude::*;
+use tiny_bail::prelude::*;
+
+use crate::{
+    GameSet,
+    assets::Levels,
+    game::level::{definition::LevelDefinition, terrain_to_world},
+    screens::Screen,
+};
+
+pub const STEEL_COLOR: Color = Color::srgb(0.55, 0.58, 0.62);
+// In front of the terrain (and any darkness), behind objects, hazards and Yups.
+const STEEL_Z: f32 = 0.6;
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::InGame), spawn_steel.in_set(GameSet::Init));
+}
+
+/// Part of the level that can't be dug through.
+#[derive(Component, Debug)]
+pub struct Steel {
+    /// In world coordinates.
+    pub area: Rect,
+}
+
+/// Whether a hole of the given radius dug at a position in the world would reach any steel.
+pub fn touches_steel<'a>(
+    steel: impl IntoIterator<Item = &'a Steel>,
+    position: Vec2,
+    radius: f32,
+) -> bool {
+    steel.into_iter().any(|steel| {
+        let nearest = position.clamp(steel.area.min, steel.area.max);
+        nearest.distance(position) < radius
+    })
+}
+
+fn spawn_steel(
+    mut commands: Commands,
+    definitions: Res<Assets<LevelDefinition>>,
+    images: Res<Assets<Image>>,
+    levels: Res<Levels>,
+) {
+    let definition = r!(definitions.get(&levels.first));
+    let terrain_size = r!(images.get(&definition.terrain_image)).size_f32();
+
+    for steel in &definition.steel {
+        let area = Rect::from_corners(
+            terrain_to_world(terrain_size, steel.position),
+            terrain_to_world(terrain_size, steel.position + steel.size),
+        );
+        commands.spawn((
+            Name::new("Steel"),
+            Steel { area },
+            Sprite::from_color(STEEL_COLOR, area.size()),
+            Transform::from_translation(area.center().extend(STEEL_Z)),
+            StateScoped(Screen::InGame),
+        ));
+    }
+}
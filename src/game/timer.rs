@@ -0,0 +1,147 @@
+use bevy::prelude::*;
+use tiny_bail::prelude::*;
+
+use crate::{
+    GameSet,
+    assets::Levels,
+    game::{Game, level::definition::LevelDefinition},
+    screens::Screen,
+    ui::format_time,
+};
+
+const TIMER_COLOR: Color = Color::WHITE;
+const TIMER_FONT_SIZE: f32 = 24.;
+// In the last half minute, the timer flashes this colour (once a second) and grows a little.
+const WARNING_COLOR: Color = Color::srgb(1., 0.25, 0.2);
+const WARNING_FONT_SIZE: f32 = 30.;
+const WARNING_SECS: f32 = 30.;
+
+pub fn plugin(app: &mut App) {
+    app.register_type::<LevelTimer>();
+    app.add_systems(
+        OnEnter(Screen::InGame),
+        (insert_level_timer, spawn_timer_text)
+            .chain()
+            .in_set(GameSet::Init),
+    );
+    app.add_systems(OnExit(Screen::InGame), remove_level_timer);
+    app.add_systems(
+        Update,
+        (
+            tick_level_timer.in_set(GameSet::TickTimers),
+            (check_level_timer, update_timer_text).in_set(GameSet::Update),
+        )
+            .run_if(resource_exists::<LevelTimer>),
+    );
+    app.add_systems(OnEnter(Game::Failed), spawn_out_of_time_text);
+}
+
+/// Counts down the time left to play the level. Only present for levels with a time limit. Like
+/// everything else in `GameSet`, it stops while the game is paused, and runs faster when the game
+/// is fast forwarded.
+#[derive(Resource, Debug, Clone, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub struct LevelTimer(pub Timer);
+
+impl LevelTimer {
+    pub fn remaining_secs(&self) -> f32 {
+        self.0.remaining_secs()
+    }
+}
+
+#[derive(Component, Debug)]
+struct TimerText;
+
+fn insert_level_timer(
+    mut commands: Commands,
+    definitions: Res<Assets<LevelDefinition>>,
+    levels: Res<Levels>,
+) {
+    let definition = r!(definitions.get(&levels.first));
+    let secs = rq!(definition.time_limit_secs);
+    commands.insert_resource(LevelTimer(Timer::from_seconds(secs, TimerMode::Once)));
+}
+
+fn remove_level_timer(mut commands: Commands) {
+    commands.remove_resource::<LevelTimer>();
+}
+
+fn spawn_timer_text(mut commands: Commands, timer: Option<Res<LevelTimer>>) {
+    if timer.is_none() {
+        return;
+    }
+    commands
+        .spawn((
+            Name::new("Timer"),
+            Node {
+                justify_content: JustifyContent::Center,
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.),
+                width: Val::Percent(100.),
+                ..default()
+            },
+            StateScoped(Screen::InGame),
+        ))
+        .with_children(|p| {
+            p.spawn((
+                Name::new("Timer Text"),
+                TimerText,
+                Text::default(),
+                TextColor(TIMER_COLOR),
+                TextFont::from_font_size(TIMER_FONT_SIZE),
+            ));
+        });
+}
+
+fn tick_level_timer(time: Res<Time>, mut timer: ResMut<LevelTimer>) {
+    timer.0.tick(time.delta());
+}
+
+fn check_level_timer(timer: Res<LevelTimer>, mut next_game: ResMut<NextState<Game>>) {
+    if timer.0.just_finished() {
+        next_game.set(Game::Failed);
+    }
+}
+
+fn update_timer_text(
+    timer: Res<LevelTimer>,
+    mut texts: Query<(&mut Text, &mut TextColor, &mut TextFont), With<TimerText>>,
+) {
+    let remaining = timer.remaining_secs();
+    // On for the first half of each second.
+    let warning = remaining <= WARNING_SECS && remaining.fract() >= 0.5;
+    for (mut text, mut color, mut font) in &mut texts {
+        text.0 = format_time(remaining);
+        color.0 = if warning { WARNING_COLOR } else { TIMER_COLOR };
+        font.font_size = if warning {
+            WARNING_FONT_SIZE
+        } else {
+            TIMER_FONT_SIZE
+        };
+    }
+}
+
+fn spawn_out_of_time_text(mut commands: Commands, timer: Option<Res<LevelTimer>>) {
+    if !timer.is_some_and(|timer| timer.0.finished()) {
+        return;
+    }
+    commands
+        .spawn((
+            Name::new("Out Of Time"),
+            Node {
+                justify_content: JustifyContent::Center,
+                position_type: PositionType::Absolute,
+                top: Val::Percent(40.),
+                width: Val::Percent(100.),
+                ..default()
+            },
+            StateScoped(Game::Failed),
+        ))
+        .with_children(|p| {
+            p.spawn((
+                Text::new("Out of time!"),
+                TextColor(WARNING_COLOR),
+                TextFont::from_font_size(WARNING_FONT_SIZE),
+            ));
+        });
+}
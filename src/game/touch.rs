@@ -0,0 +1,332 @@
+use bevy::{
+    ecs::system::EntityCommands,
+    input::touch::Touches,
+    picking::{focus::HoverMap, pointer::PointerId},
+    prelude::*,
+};
+use tiny_bail::prelude::*;
+
+use crate::{
+    GameSet, MainCamera,
+    game::{
+        Game,
+        camera::{self, CameraTarget, CameraZoom, ZOOM_MIN, max_scale},
+        cursor::{self, CursorSource, GameCursor},
+        level::Level,
+        minimap::{MINIMAP_MARGIN, MINIMAP_WIDTH},
+        skills::{SKILL_TEXT_WIDTH, SelectedSkill},
+        speed::GameSpeed,
+    },
+    screens::Screen,
+    ui::{Widgets, pointer_id_over_ui},
+    viewport::window_to_viewport,
+};
+
+// A finger needs to stay down this long before it starts digging, so that the first finger of a
+// pinch doesn't leave a hole behind.
+const HOLD_SECS: f32 = 0.15;
+// Big enough for a thumb.
+const TOUCH_BUTTON_SIZE: f32 = 72.;
+// A touch that is lifted sooner than this, without moving far, counts as a tap.
+const TAP_MAX_SECS: f32 = 0.3;
+// In logical pixels.
+const TAP_MAX_DISTANCE: f32 = 12.;
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<TouchGesture>();
+    app.add_systems(
+        OnEnter(Screen::InGame),
+        spawn_touch_controls.in_set(GameSet::Init),
+    );
+    app.add_systems(
+        Update,
+        touch_cursor
+            .in_set(GameSet::RecordInput)
+            .after(cursor::track_mouse)
+            .before(cursor::update_world_position),
+    );
+    app.add_systems(
+        Update,
+        (
+            touch_pan_zoom.before(camera::clamp_target),
+            show_touch_controls,
+        )
+            .run_if(in_state(Screen::InGame).or(in_state(Screen::Editor))),
+    );
+}
+
+/// What the fingers currently on the screen are up to.
+#[derive(Resource, Debug, Default)]
+struct TouchGesture {
+    /// Set as soon as a second finger comes down, and kept until every finger is lifted, so that a
+    /// pinch never turns into a tap.
+    multi_touch: bool,
+    /// Set if the finger went anywhere near the UI, which handles the touch itself.
+    on_ui: bool,
+    /// The zoom target and finger spacing when the current pinch began.
+    pinch_start: Option<(f32, f32)>,
+    /// When the current touch began, in real time so fast forward makes no difference.
+    started_secs: f32,
+}
+
+/// Larger on-screen buttons for things otherwise done with keys.
+#[derive(Component, Debug)]
+struct TouchControls;
+
+fn spawn_touch_controls(mut commands: Commands) {
+    commands
+        .spawn((
+            Name::new("Touch Controls"),
+            TouchControls,
+            // Along the bottom, between the skill text and the minimap. Buttons that don't fit
+            // wrap onto another row above.
+            Node {
+                bottom: Val::Px(10.),
+                column_gap: Val::Px(12.),
+                flex_wrap: FlexWrap::WrapReverse,
+                justify_content: JustifyContent::Center,
+                left: Val::Px(10. + SKILL_TEXT_WIDTH),
+                position_type: PositionType::Absolute,
+                right: Val::Px(MINIMAP_WIDTH + MINIMAP_MARGIN * 2.),
+                row_gap: Val::Px(12.),
+                ..default()
+            },
+            // Only the buttons themselves should get in the way of touching the level.
+            PickingBehavior::IGNORE,
+            StateScoped(Screen::InGame),
+            // Shown once the screen is first touched.
+            Visibility::Hidden,
+        ))
+        .with_children(|p| {
+            touch_button(p, "Prev").observe(
+                |_trigger: Trigger<Pointer<Click>>, mut selected: ResMut<SelectedSkill>| {
+                    selected.cycle(-1);
+                },
+            );
+            touch_button(p, "Next").observe(
+                |_trigger: Trigger<Pointer<Click>>, mut selected: ResMut<SelectedSkill>| {
+                    selected.cycle(1);
+                },
+            );
+            touch_button(p, "Fast").observe(
+                |_trigger: Trigger<Pointer<Click>>, mut speed: ResMut<GameSpeed>| {
+                    speed.toggle();
+                },
+            );
+            touch_button(p, "Pause").observe(
+                |_trigger: Trigger<Pointer<Click>>, mut game: ResMut<NextState<Game>>| {
+                    game.set(Game::Paused);
+                },
+            );
+        });
+}
+
+// The usual button, made big enough for a thumb.
+fn touch_button<'a>(p: &'a mut ChildBuilder, text: &str) -> EntityCommands<'a> {
+    let mut button = p.button(text);
+    button.insert(BorderRadius::all(Val::Px(8.)));
+    button.entry::<Node>().and_modify(|mut node| {
+        node.min_height = Val::Px(TOUCH_BUTTON_SIZE);
+        node.min_width = Val::Px(TOUCH_BUTTON_SIZE);
+    });
+    button
+}
+
+fn show_touch_controls(
+    mut controls: Query<&mut Visibility, With<TouchControls>>,
+    touches: Res<Touches>,
+) {
+    if !touches.any_just_pressed() {
+        return;
+    }
+    for mut visibility in &mut controls {
+        visibility.set_if_neq(Visibility::Inherited);
+    }
+}
+
+// A single finger acts like the mouse: tapping selects once, holding selects continuously.
+fn touch_cursor(
+    camera: Single<&Camera, With<MainCamera>>,
+    mut cursor: ResMut<GameCursor>,
+    mut gesture: ResMut<TouchGesture>,
+    hover_map: Res<HoverMap>,
+    nodes: Query<(), With<Node>>,
+    time: Res<Time<Real>>,
+    touches: Res<Touches>,
+) {
+    let down = touches.iter().count();
+    let released = touches.iter_just_released().next();
+    if down == 0 && released.is_none() {
+        // Every finger has been lifted, ready for the next gesture.
+        *gesture = TouchGesture::default();
+        return;
+    }
+    if down > 1 {
+        gesture.multi_touch = true;
+    }
+    if touches.any_just_pressed() && down == 1 {
+        gesture.started_secs = time.elapsed_secs();
+    }
+    if gesture.multi_touch {
+        return;
+    }
+
+    let touch = rq!(touches.iter().next().or(released));
+    gesture.on_ui |= pointer_id_over_ui(&hover_map, &nodes, PointerId::Touch(touch.id()));
+    if gesture.on_ui {
+        return;
+    }
+
+    cursor.source = CursorSource::Touch;
+    cursor.viewport_position = Some(window_to_viewport(&camera, touch.position()));
+
+    let held_secs = time.elapsed_secs() - gesture.started_secs;
+    let lifted = touches.just_released(touch.id());
+    let tapped = lifted && held_secs < TAP_MAX_SECS && touch.distance().length() < TAP_MAX_DISTANCE;
+    cursor.select_just_pressed = tapped;
+    cursor.select_pressed = tapped || (!lifted && held_secs >= HOLD_SECS);
+}
+
+// Two fingers drag the level around and pinch to zoom.
+fn touch_pan_zoom(
+    camera: Single<(&Camera, &OrthographicProjection), With<MainCamera>>,
+    mut gesture: ResMut<TouchGesture>,
+    level: Query<&Level>,
+    mut target: ResMut<CameraTarget>,
+    touches: Res<Touches>,
+    mut zoom: ResMut<CameraZoom>,
+) {
+    let mut fingers = touches.iter();
+    let (Some(a), Some(b)) = (fingers.next(), fingers.next()) else {
+        gesture.pinch_start = None;
+        return;
+    };
+
+    let (cam, projection) = *camera;
+    let viewport_width = rq!(cam.logical_viewport_size()).x;
+    let world_per_pixel = projection.area.width() / viewport_width;
+    let midpoint = (a.position() + b.position()) / 2.;
+    let previous_midpoint = (a.previous_position() + b.previous_position()) / 2.;
+    let delta = midpoint - previous_midpoint;
+    // Window coordinates have y pointing down, world coordinates have it pointing up.
+    **target += Vec2::new(-delta.x, delta.y) * world_per_pixel;
+
+    let level = rq!(level.get_single());
+    let max = max_scale(level.size);
+    let spacing = a.position().distance(b.position()).max(1.);
+    let (start_scale, start_spacing) = *gesture.pinch_start.get_or_insert((zoom.target, spacing));
+    // Fingers moving apart zoom in, which means a smaller scale.
+    let scale = (start_scale * start_spacing / spacing).clamp(ZOOM_MIN, max);
+    zoom.target = if zoom.pixel_perfect {
+        CameraZoom::nearest_pixel_perfect(scale, max)
+    } else {
+        scale
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::{
+        input::{
+            InputPlugin,
+            touch::{TouchInput, TouchPhase},
+        },
+        time::TimeUpdateStrategy,
+    };
+
+    use super::*;
+
+    // Each update moves time on by this much.
+    const FRAME_SECS: f32 = 0.1;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, InputPlugin));
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            FRAME_SECS,
+        )));
+        app.init_resource::<GameCursor>();
+        app.init_resource::<HoverMap>();
+        app.init_resource::<TouchGesture>();
+        app.world_mut().spawn((Camera::default(), MainCamera));
+        app.add_systems(Update, touch_cursor);
+        // Start the clock, so that the first touch isn't at time zero.
+        app.update();
+        app
+    }
+
+    fn touch(app: &mut App, id: u64, phase: TouchPhase, position: Vec2) {
+        app.world_mut().send_event(TouchInput {
+            force: None,
+            id,
+            phase,
+            position,
+            window: Entity::PLACEHOLDER,
+        });
+    }
+
+    // Well past both the hold time and the tap time.
+    fn hold(app: &mut App) {
+        for _ in 0..((HOLD_SECS + TAP_MAX_SECS) / FRAME_SECS) as usize + 1 {
+            app.update();
+        }
+    }
+
+    #[test]
+    fn tap_selects_once() {
+        let mut app = app();
+        let position = Vec2::new(100., 50.);
+
+        touch(&mut app, 0, TouchPhase::Started, position);
+        app.update();
+        let cursor = app.world().resource::<GameCursor>();
+        assert_eq!(cursor.source, CursorSource::Touch);
+        assert_eq!(cursor.viewport_position, Some(position));
+        assert!(!cursor.select_pressed);
+
+        touch(&mut app, 0, TouchPhase::Ended, position);
+        app.update();
+        let cursor = app.world().resource::<GameCursor>();
+        assert!(cursor.select_just_pressed);
+        assert!(cursor.select_pressed);
+    }
+
+    #[test]
+    fn holding_selects_continuously() {
+        let mut app = app();
+        let position = Vec2::new(100., 50.);
+
+        touch(&mut app, 0, TouchPhase::Started, position);
+        app.update();
+        hold(&mut app);
+        let cursor = app.world().resource::<GameCursor>();
+        assert!(cursor.select_pressed);
+        assert!(!cursor.select_just_pressed);
+
+        // Lifted too late to count as a tap.
+        touch(&mut app, 0, TouchPhase::Ended, position);
+        app.update();
+        let cursor = app.world().resource::<GameCursor>();
+        assert!(!cursor.select_pressed);
+        assert!(!cursor.select_just_pressed);
+    }
+
+    #[test]
+    fn pinch_never_selects() {
+        let mut app = app();
+
+        touch(&mut app, 0, TouchPhase::Started, Vec2::new(100., 50.));
+        touch(&mut app, 1, TouchPhase::Started, Vec2::new(200., 50.));
+        app.update();
+        hold(&mut app);
+        touch(&mut app, 0, TouchPhase::Ended, Vec2::new(80., 50.));
+        touch(&mut app, 1, TouchPhase::Ended, Vec2::new(220., 50.));
+        app.update();
+
+        let cursor = app.world().resource::<GameCursor>();
+        assert!(!cursor.select_pressed);
+        assert!(!cursor.select_just_pressed);
+    }
+}
@@ -0,0 +1,220 @@
+use bevy::{prelude::*, ui::Val::*};
+use serde::{Deserialize, Serialize};
+use tiny_bail::prelude::*;
+
+use crate::{
+    GameSet,
+    assets::Levels,
+    audio::MusicOverride,
+    dialogue::{DialogueLine, StartDialogue},
+    game::{
+        exits::YupRescued,
+        level::{definition::LevelDefinition, terrain_to_world},
+        objects::LevelObject,
+        stats::LevelStats,
+        yup::{CharacterState, SpawnYup, Yup, feet},
+    },
+    screens::Screen,
+};
+
+// How long a caption stays on screen.
+const CAPTION_SECS: f32 = 4.;
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<Script>();
+    app.add_systems(OnEnter(Screen::InGame), reset_script.in_set(GameSet::Init));
+    app.add_systems(
+        Update,
+        (run_triggers, expire_captions)
+            .chain()
+            .in_set(GameSet::Update),
+    );
+}
+
+/// What has to happen for a trigger to fire. Each trigger only fires once.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum Condition {
+    /// Once this many seconds of the level have been played.
+    After(f32),
+    /// Once at least this many Yups have made it home.
+    Rescued(u32),
+    /// Once any living Yup has their feet inside the area, given by its top left corner and size.
+    YupEnters { position: Vec2, size: Vec2 },
+}
+
+impl Condition {
+    /// The corners of the area the condition looks at, if any.
+    pub fn corners(&self) -> Option<[Vec2; 2]> {
+        match self {
+            Self::YupEnters { position, size } => Some([*position, *position + *size]),
+            Self::After(_) | Self::Rescued(_) => None,
+        }
+    }
+}
+
+/// What a trigger does once it fires.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum Action {
+    /// Shows some dialogue, with the level waiting until it's finished.
+    Dialogue(Vec<DialogueLine>),
+    /// Switches to the music at the given asset path, for the rest of the level.
+    PlayMusic(String),
+    /// Shows a line of text across the top of the screen.
+    Say(String),
+    /// Makes every level object with the id active or not, such as to open a door.
+    SetObject { id: String, active: bool },
+    /// Releases extra Yups all at once.
+    SpawnYups { position: Vec2, count: u32 },
+}
+
+impl Action {
+    /// The id of the level object acted on, if any.
+    pub fn object(&self) -> Option<&str> {
+        match self {
+            Self::SetObject { id, .. } => Some(id),
+            Self::Dialogue(_) | Self::PlayMusic(_) | Self::Say(_) | Self::SpawnYups { .. } => None,
+        }
+    }
+
+    /// Where in the level the action happens, if anywhere.
+    pub fn position(&self) -> Option<&Vec2> {
+        match self {
+            Self::SpawnYups { position, .. } => Some(position),
+            Self::Dialogue(_) | Self::PlayMusic(_) | Self::Say(_) | Self::SetObject { .. } => None,
+        }
+    }
+}
+
+/// How far the current level's script has got.
+#[derive(Resource, Debug, Default)]
+struct Script {
+    /// Seconds of play so far. Stops while the game is paused.
+    elapsed_secs: f32,
+    /// Whether each of the level's triggers has fired, in the same order.
+    fired: Vec<bool>,
+    /// How many Yups have made it home so far.
+    rescued: u32,
+}
+
+/// A line of text shown by [`Action::Say`], removed when the timer finishes.
+#[derive(Component, Debug)]
+struct Caption(Timer);
+
+fn reset_script(
+    definitions: Res<Assets<LevelDefinition>>,
+    levels: Res<Levels>,
+    mut script: ResMut<Script>,
+) {
+    let definition = r!(definitions.get(&levels.first));
+    *script = Script {
+        elapsed_secs: 0.,
+        fired: vec![false; definition.triggers.len()],
+        rescued: 0,
+    };
+}
+
+fn run_triggers(
+    mut commands: Commands,
+    captions: Query<Entity, With<Caption>>,
+    definitions: Res<Assets<LevelDefinition>>,
+    mut dialogue: EventWriter<StartDialogue>,
+    images: Res<Assets<Image>>,
+    levels: Res<Levels>,
+    mut music: ResMut<MusicOverride>,
+    mut objects: Query<&mut LevelObject>,
+    mut rescues: EventReader<YupRescued>,
+    mut script: ResMut<Script>,
+    mut spawns: EventWriter<SpawnYup>,
+    mut stats: ResMut<LevelStats>,
+    time: Res<Time>,
+    yups: Query<(&CharacterState, &Transform), With<Yup>>,
+) {
+    let definition = r!(definitions.get(&levels.first));
+    let terrain_size = r!(images.get(&definition.terrain_image)).size_f32();
+    script.elapsed_secs += time.delta_secs();
+    script.rescued += rescues.read().count() as u32;
+
+    for (i, trigger) in definition.triggers.iter().enumerate() {
+        if script.fired.get(i) != Some(&false) {
+            continue;
+        }
+        let met = match &trigger.when {
+            Condition::After(secs) => script.elapsed_secs >= *secs,
+            Condition::Rescued(count) => script.rescued >= *count,
+            Condition::YupEnters { position, size } => {
+                let area = Rect::from_corners(
+                    terrain_to_world(terrain_size, *position),
+                    terrain_to_world(terrain_size, *position + *size),
+                );
+                // Checked at the Yup's feet, like exits, hazards and objects.
+                yups.iter().any(|(state, transform)| {
+                    !matches!(state, CharacterState::Dead(_)) && area.contains(feet(transform))
+                })
+            }
+        };
+        if !met {
+            continue;
+        }
+        script.fired[i] = true;
+
+        for action in &trigger.then {
+            match action {
+                Action::Dialogue(lines) => {
+                    dialogue.send(StartDialogue(lines.clone()));
+                }
+                Action::PlayMusic(path) => {
+                    music.0 = Some(path.clone());
+                }
+                Action::Say(text) => {
+                    // Only the latest caption is shown.
+                    for entity in &captions {
+                        commands.entity(entity).despawn_recursive();
+                    }
+                    spawn_caption(&mut commands, text);
+                }
+                Action::SetObject { id, active } => {
+                    for mut object in objects.iter_mut().filter(|o| o.id == *id) {
+                        object.active = *active;
+                    }
+                }
+                Action::SpawnYups { position, count } => {
+                    let position = terrain_to_world(terrain_size, *position);
+                    spawns.send_batch((0..*count).map(|_| SpawnYup { position }));
+                    // Counted along with the hatches' Yups, so the stats still add up.
+                    stats.total += count;
+                }
+            }
+        }
+    }
+}
+
+fn spawn_caption(commands: &mut Commands, text: &str) {
+    commands
+        .spawn((
+            Name::new("Caption"),
+            Caption(Timer::from_seconds(CAPTION_SECS, TimerMode::Once)),
+            Node {
+                justify_content: JustifyContent::Center,
+                position_type: PositionType::Absolute,
+                top: Percent(10.),
+                width: Percent(100.),
+                ..default()
+            },
+            StateScoped(Screen::InGame),
+        ))
+        .with_children(|p| {
+            p.spawn((Name::new("Caption Text"), Text::new(text)));
+        });
+}
+
+fn expire_captions(
+    mut commands: Commands,
+    mut captions: Query<(Entity, &mut Caption)>,
+    time: Res<Time>,
+) {
+    for (entity, mut caption) in &mut captions {
+        if caption.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -1,31 +1,228 @@
 use bevy::prelude::*;
+use tiny_bail::prelude::*;
 
-use crate::{GameSet, assets::Characters, physics::Gravity, screens::Screen};
+use crate::{
+    GameSet,
+    assets::Characters,
+    game::{
+        Game,
+        animation::{self, SpriteAnimation, sheet::AnimationSheet},
+        level::Level,
+    },
+    physics::{
+        Gravity,
+        collision::{YUP_COUNT, YUP_FEET_FACTOR},
+    },
+};
+
+/// How close (in world units) a walker can get to a blocker, side to side, before turning back.
+const BLOCK_REACH: f32 = 10.;
+// Half the width of a frame in `assets/animations/yup.anim.ron`.
+const YUP_HALF_WIDTH: f32 = 10.;
 
 #[derive(Component, Debug, Default, Eq, PartialEq)]
 pub enum CharacterState {
+    /// Standing still, holding back the Yups behind.
+    Blocking,
+    /// No longer with us, and about to be removed from the level.
+    Dead(Death),
     #[default]
     Falling,
     Walking,
 }
 
+/// How a Yup met their end.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Death {
+    Burned,
+    Crushed,
+    Drowned,
+    Impaled,
+}
+
+impl CharacterState {
+    /// What the Yup is up to, as shown to the player.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Blocking => "Blocker",
+            Self::Dead(_) => "Dead",
+            Self::Falling => "Faller",
+            Self::Walking => "Walker",
+        }
+    }
+
+    /// The name of the animation clip to play in this state, from `assets/animations/yup.anim.ron`.
+    /// Digging is done by the player rather than by a Yup, so there's no clip for it. Swimmers walk
+    /// with a clip of their own.
+    pub fn clip(&self) -> &'static str {
+        match self {
+            Self::Blocking => "block",
+            Self::Dead(Death::Burned) => "burn",
+            Self::Dead(Death::Drowned) => "drown",
+            Self::Dead(Death::Crushed | Death::Impaled) => "splat",
+            Self::Falling => "fall",
+            Self::Walking => "walk",
+        }
+    }
+}
+
+/// Which way a Yup walks, and so which way their sprite faces.
+#[derive(Component, Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Facing {
+    Left,
+    #[default]
+    Right,
+}
+
+impl Facing {
+    /// -1 for left, 1 for right.
+    pub fn sign(&self) -> f32 {
+        match self {
+            Self::Left => -1.,
+            Self::Right => 1.,
+        }
+    }
+
+    pub fn reversed(&self) -> Self {
+        match self {
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+        }
+    }
+}
+
 #[derive(Component, Debug)]
-#[require(CharacterState, Gravity)]
+#[require(CharacterState, Facing, Gravity)]
 pub struct Yup;
 
+/// Can cross water without drowning.
+#[derive(Component, Debug)]
+pub struct Swimmer;
+
+/// Where a Yup stands, in world coordinates. Collisions with the terrain, hazards, exits, objects
+/// and triggers are all checked here, rather than at the middle of their sprite.
+pub fn feet(transform: &Transform) -> Vec2 {
+    transform.translation.truncate() - Vec2::Y * YUP_FEET_FACTOR
+}
+
+/// Send this to put a new Yup in the level, at a position in the world.
+#[derive(Event, Debug)]
+pub struct SpawnYup {
+    pub position: Vec2,
+}
+
 pub fn plugin(app: &mut App) {
-    app.add_systems(OnEnter(Screen::InGame), init.in_set(GameSet::Init));
+    app.add_event::<SpawnYup>();
+    app.add_systems(Update, spawn_yups.in_set(GameSet::Update));
+    app.add_systems(
+        FixedUpdate,
+        (
+            turn_at_blockers,
+            turn_at_level_edges,
+            animate.before(animation::advance_animations),
+        )
+            .chain()
+            .run_if(in_state(Game::Playing)),
+    );
 }
 
-fn init(mut commands: Commands, characters: Res<Characters>) {
-    commands.spawn((
+fn spawn_yups(
+    mut commands: Commands,
+    characters: Res<Characters>,
+    mut events: EventReader<SpawnYup>,
+    sheets: Res<Assets<AnimationSheet>>,
+    yups: Query<(), With<Yup>>,
+) {
+    let sheet = r!(sheets.get(&characters.yup));
+    // Collisions are only worked out for so many Yups, so any more are turned away.
+    let room = YUP_COUNT.saturating_sub(yups.iter().len());
+    for SpawnYup { position } in events.read().take(room) {
+        commands.spawn(yup(&characters, sheet, *position));
+    }
+}
+
+fn yup(characters: &Characters, sheet: &AnimationSheet, position: Vec2) -> impl Bundle {
+    (
         Name::new("Yup"),
         Yup,
-        Sprite {
-            image: characters.yup.clone(),
-            ..default()
-        },
+        SpriteAnimation::new(characters.yup.clone(), CharacterState::default().clip()),
+        // Replaced by the animation's first frame as soon as it starts playing.
+        Sprite::from_atlas_image(sheet.image_handle.clone(), TextureAtlas {
+            index: 0,
+            layout: sheet.layout.clone(),
+        }),
         // TODO: should all yups be spawned on specific Z-value for easy handling?
-        Transform::from_xyz(0., 800., 1.),
-    ));
+        Transform::from_translation(position.extend(1.)),
+    )
+}
+
+// Walkers who run into a blocker head back the way they came. Those already walking away are left
+// alone, so that they don't turn back and forth while they're still within reach.
+fn turn_at_blockers(
+    mut walkers: Query<(&CharacterState, &mut Facing, &Transform), With<Yup>>,
+    yups: Query<(&CharacterState, &Transform), With<Yup>>,
+) {
+    let blockers: Vec<Vec2> = yups
+        .iter()
+        .filter(|(state, _)| **state == CharacterState::Blocking)
+        .map(|(_, t)| t.translation.truncate())
+        .collect();
+    if blockers.is_empty() {
+        return;
+    }
+
+    for (state, mut facing, t) in &mut walkers {
+        if *state != CharacterState::Walking {
+            continue;
+        }
+        let position = t.translation.truncate();
+        let blocked = blockers.iter().any(|blocker| {
+            let offset = *blocker - position;
+            // Only blockers on the same footing, and ahead of the walker.
+            offset.x.abs() < BLOCK_REACH
+                && offset.y.abs() < BLOCK_REACH * 2.
+                && offset.x * facing.sign() > 0.
+        });
+        if blocked {
+            *facing = facing.reversed();
+        }
+    }
+}
+
+// Walkers who reach either side of the level head back the way they came, rather than walking off
+// into nothing.
+fn turn_at_level_edges(
+    level: Query<&Level>,
+    mut walkers: Query<(&CharacterState, &mut Facing, &Transform), With<Yup>>,
+) {
+    let level = rq!(level.get_single());
+    let edge = level.size.x / 2. - YUP_HALF_WIDTH;
+    for (state, mut facing, t) in &mut walkers {
+        if *state == CharacterState::Walking && t.translation.x * facing.sign() >= edge {
+            *facing = facing.reversed();
+        }
+    }
+}
+
+fn animate(
+    mut yups: Query<(
+        &CharacterState,
+        &Facing,
+        Has<Swimmer>,
+        &mut Sprite,
+        &mut SpriteAnimation,
+    )>,
+) {
+    for (state, facing, swimmer, mut sprite, mut animation) in &mut yups {
+        // Swimmers walk in a rubber ring, so that they can be told apart from everyone else.
+        animation.play(match state {
+            CharacterState::Walking if swimmer => "swim",
+            _ => state.clip(),
+        });
+        // Sprites are drawn facing right.
+        let flip_x = *facing == Facing::Left;
+        if sprite.flip_x != flip_x {
+            sprite.flip_x = flip_x;
+        }
+    }
 }
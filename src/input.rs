@@ -0,0 +1,88 @@
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Config, KeyBindings};
+
+pub fn plugin(app: &mut App) {
+    app.add_plugins(InputManagerPlugin::<GameAction>::default());
+    app.init_resource::<ActionState<GameAction>>();
+    // Filled in from the config on the first frame, see `update_input_map`.
+    app.init_resource::<InputMap<GameAction>>();
+    app.add_systems(
+        PostUpdate,
+        update_input_map.run_if(resource_changed::<Config>),
+    );
+}
+
+/// Everything the player can do, whatever they're doing it with. Systems should read these from
+/// `ActionState<GameAction>` rather than checking keys or buttons themselves.
+#[derive(Actionlike, Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Reflect, Serialize)]
+pub enum GameAction {
+    /// Shows the rest of the current line of dialogue, or moves on to the next.
+    AdvanceDialogue,
+    /// Hold to move the camera by dragging the mouse.
+    DragPan,
+    FastForward,
+    /// Moves the virtual cursor, for playing without a mouse.
+    #[actionlike(DualAxis)]
+    MoveCursor,
+    NextSkill,
+    #[actionlike(DualAxis)]
+    Pan,
+    Pause,
+    PreviousSkill,
+    ReleaseFaster,
+    ReleaseSlower,
+    /// Removes whatever is under the cursor, in the editor.
+    Remove,
+    /// Uses the selected skill, paints or places whatever is under the cursor.
+    Select,
+    /// Skips the rest of the current dialogue.
+    SkipDialogue,
+    SkipSplash,
+    ToggleDebug,
+}
+
+impl GameAction {
+    /// Builds the input map for the player's key bindings. Mouse and gamepad bindings are fixed.
+    pub fn input_map(keys: &KeyBindings) -> InputMap<Self> {
+        InputMap::default()
+            .with(Self::AdvanceDialogue, keys.advance_dialogue)
+            .with(Self::AdvanceDialogue, MouseButton::Left)
+            .with(Self::AdvanceDialogue, GamepadButton::South)
+            .with(Self::DragPan, MouseButton::Middle)
+            .with(Self::FastForward, keys.fast_forward)
+            .with(Self::FastForward, GamepadButton::North)
+            .with_dual_axis(Self::MoveCursor, GamepadStick::LEFT)
+            .with(Self::NextSkill, keys.next_skill)
+            .with(Self::NextSkill, GamepadButton::RightTrigger2)
+            .with_dual_axis(
+                Self::Pan,
+                VirtualDPad::new(keys.pan_up, keys.pan_down, keys.pan_left, keys.pan_right),
+            )
+            .with_dual_axis(Self::Pan, VirtualDPad::dpad())
+            .with_dual_axis(Self::Pan, GamepadStick::RIGHT)
+            .with(Self::Pause, keys.pause)
+            .with(Self::Pause, GamepadButton::Start)
+            .with(Self::PreviousSkill, keys.previous_skill)
+            .with(Self::PreviousSkill, GamepadButton::LeftTrigger2)
+            .with(Self::ReleaseFaster, keys.release_faster)
+            .with(Self::ReleaseFaster, GamepadButton::RightTrigger)
+            .with(Self::ReleaseSlower, keys.release_slower)
+            .with(Self::ReleaseSlower, GamepadButton::LeftTrigger)
+            .with(Self::Remove, MouseButton::Right)
+            .with(Self::Remove, GamepadButton::West)
+            .with(Self::Select, MouseButton::Left)
+            .with(Self::Select, GamepadButton::South)
+            .with(Self::SkipDialogue, keys.skip_dialogue)
+            .with(Self::SkipDialogue, GamepadButton::East)
+            .with(Self::SkipSplash, keys.skip_splash)
+            .with(Self::SkipSplash, GamepadButton::Start)
+            .with(Self::ToggleDebug, KeyCode::Backquote)
+    }
+}
+
+fn update_input_map(config: Res<Config>, mut input_map: ResMut<InputMap<GameAction>>) {
+    *input_map = GameAction::input_map(&config.keys);
+}
@@ -1,25 +1,47 @@
-mod assets;
+pub mod assets;
+pub mod audio;
+pub mod config;
 #[cfg(feature = "dev")]
 mod dev_tools;
+pub mod dialogue;
 pub mod game;
+pub mod input;
 pub mod physics;
 pub mod screens;
 mod ui;
+pub mod viewport;
+
+use std::time::Duration;
 
 use bevy::{
+    app::ScheduleRunnerPlugin,
     asset::AssetMetaCheck,
     audio::{AudioPlugin, Volume},
+    log::{Level, LogPlugin},
     prelude::*,
-    render::view::RenderLayers,
-    window::WindowResolution,
+    render::{camera::ScalingMode, view::RenderLayers},
+    time::TimeUpdateStrategy,
+    window::{ExitCondition, WindowResolution},
+    winit::WinitPlugin,
 };
+use config::{Config, ConfigErrors};
 use game::{Game, rendering::GameRenderLayers};
 use screens::Screen;
+use viewport::VIRTUAL_RESOLUTION;
 
-pub struct GamePlugin;
+#[derive(Default)]
+pub struct GamePlugin {
+    /// Runs without a window, as fast as the machine allows, for tools such as `verify_levels`.
+    /// Every frame moves the game on by exactly one fixed timestep, so that runs are repeatable.
+    pub headless: bool,
+}
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
+        // The window and audio plugins need the config before the app is even running, so load it
+        // up front rather than as an asset.
+        let (config, errors) = Config::load();
+
         app.configure_sets(OnEnter(Screen::InGame), GameSet::Init);
         app.configure_sets(
             Update,
@@ -36,44 +58,73 @@ impl Plugin for GamePlugin {
 
         app.add_systems(Startup, spawn_camera);
 
-        app.add_plugins(
-            DefaultPlugins
-                .set(AssetPlugin {
-                    // Wasm builds will check for meta files (that don't exist) if this isn't set.
-                    // This causes errors and even panics on web build on itch.
-                    // See https://github.com/bevyengine/bevy_github_ci_template/issues/48.
-                    meta_check: AssetMetaCheck::Never,
+        let plugins = DefaultPlugins
+            .set(AssetPlugin {
+                // Wasm builds will check for meta files (that don't exist) if this isn't set.
+                // This causes errors and even panics on web build on itch.
+                // See https://github.com/bevyengine/bevy_github_ci_template/issues/48.
+                meta_check: AssetMetaCheck::Never,
+                ..default()
+            })
+            .set(ImagePlugin::default_nearest())
+            .set(WindowPlugin {
+                primary_window: Window {
+                    title: "all the way home".to_string(),
+                    canvas: Some("#bevy".to_string()),
+                    fit_canvas_to_parent: true,
+                    prevent_default_event_handling: true,
+                    mode: config.window.mode(),
+                    present_mode: config.window.present_mode(),
+                    resolution: WindowResolution::new(config.window.width, config.window.height),
                     ..default()
-                })
-                .set(ImagePlugin::default_nearest())
-                .set(WindowPlugin {
-                    primary_window: Window {
-                        title: "all the way home".to_string(),
-                        canvas: Some("#bevy".to_string()),
-                        fit_canvas_to_parent: true,
-                        prevent_default_event_handling: true,
-                        resizable: false,
-                        // TODO: do these need to be hardcoded, or can we set them via `Config`?
-                        resolution: WindowResolution::new(1280., 720.)
-                            .with_scale_factor_override(1.0),
+                }
+                .into(),
+                ..default()
+            })
+            .set(AudioPlugin {
+                global_volume: GlobalVolume {
+                    volume: Volume::new(config.audio.master_volume),
+                },
+                ..default()
+            });
+
+        if self.headless {
+            app.add_plugins(
+                plugins
+                    .set(LogPlugin {
+                        level: Level::WARN,
                         ..default()
-                    }
-                    .into(),
-                    ..default()
-                })
-                .set(AudioPlugin {
-                    global_volume: GlobalVolume {
-                        volume: Volume::new(0.3),
-                    },
-                    ..default()
-                }),
-        );
+                    })
+                    .set(WindowPlugin {
+                        primary_window: None,
+                        exit_condition: ExitCondition::DontExit,
+                        close_when_requested: false,
+                    })
+                    .disable::<WinitPlugin>(),
+            );
+            app.add_plugins(ScheduleRunnerPlugin::run_loop(Duration::ZERO));
+            app.insert_resource(TimeUpdateStrategy::ManualDuration(
+                Time::<Fixed>::default().timestep(),
+            ));
+        } else {
+            app.add_plugins(plugins);
+        }
+
+        app.insert_resource(ConfigErrors(
+            errors.iter().map(ToString::to_string).collect(),
+        ));
+        app.insert_resource(config);
 
         app.add_plugins((
             assets::plugin,
+            audio::plugin,
+            config::plugin,
+            dialogue::plugin,
             game::plugin,
+            input::plugin,
             physics::plugin,
             screens::plugin,
+            viewport::plugin,
         ));
 
         #[cfg(feature = "dev")]
@@ -106,11 +157,22 @@ fn spawn_camera(mut commands: Commands) {
         MainCamera,
         Camera2d,
         IsDefaultUiCamera,
+        // Always show the same area of the world, however large the window. See `viewport`.
+        OrthographicProjection {
+            scaling_mode: ScalingMode::Fixed {
+                width: VIRTUAL_RESOLUTION.x,
+                height: VIRTUAL_RESOLUTION.y,
+            },
+            ..OrthographicProjection::default_2d()
+        },
         // This camera needs to be able to see all our render layers in order to composite the
         // level background and the sprites together into one view.
         RenderLayers::from_layers(&[
             GameRenderLayers::Main.into(),
             GameRenderLayers::Terrain.into(),
+            GameRenderLayers::Background.into(),
         ]),
+        // Hears sounds from the level, see `audio`.
+        SpatialListener::default(),
     ));
 }
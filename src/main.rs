@@ -4,5 +4,5 @@ use bevy::prelude::*;
 use home::GamePlugin;
 
 fn main() {
-    App::new().add_plugins(GamePlugin).run();
+    App::new().add_plugins(GamePlugin::default()).run();
 }
@@ -3,25 +3,28 @@ pub mod collision;
 use bevy::prelude::*;
 use collision::CollisionPlugin;
 
-use crate::game::yup::CharacterState;
+use crate::game::{
+    Game,
+    yup::{CharacterState, Facing},
+};
 
 pub fn plugin(app: &mut App) {
     app.add_plugins(CollisionPlugin);
-    app.add_systems(FixedUpdate, gravity);
+    app.add_systems(FixedUpdate, gravity.run_if(in_state(Game::Playing)));
 }
 
 #[derive(Component, Debug, Default)]
 pub struct Gravity;
 
-fn gravity(mut has_gravity: Query<(&CharacterState, &mut Transform), With<Gravity>>) {
-    for (state, mut t) in &mut has_gravity {
+fn gravity(mut has_gravity: Query<(&CharacterState, &Facing, &mut Transform), With<Gravity>>) {
+    for (state, facing, mut t) in &mut has_gravity {
         if *state == CharacterState::Falling {
             // TODO: delta time
             t.translation.y -= 3.0;
         }
 
         if *state == CharacterState::Walking {
-            t.translation.x += 2.0;
+            t.translation.x += 2.0 * facing.sign();
         }
     }
 }
@@ -17,17 +17,19 @@ use binding_types::{storage_buffer, texture_storage_2d, uniform_buffer};
 use tiny_bail::prelude::*;
 
 use crate::game::{
-    level::{Level, LevelRenderTargets},
-    yup::{CharacterState, Yup},
+    Game,
+    hazards::{Hazard, HazardKind, hazard_at},
+    level::{Level, LevelRenderTargets, world_to_terrain},
+    yup::{CharacterState, Swimmer, Yup, feet},
 };
 
 const SHADER_ASSET_PATH: &str = "shaders/collision.wgsl";
-const YUP_COUNT: usize = 100;
+pub const YUP_COUNT: usize = 100;
 // NOTE: actual number of u32's is 400, but this supports byte alignment via use of Vec4.
 // In other words, we're passing 400 values, the last of each 4 will be ignored as padding.
 const YUP_BUFFER_SIZE: usize = 100;
 // Offset to the centre of the Yup sprite, to reflect the position of their feet!
-const YUP_FEET_FACTOR: f32 = 18.;
+pub const YUP_FEET_FACTOR: f32 = 18.;
 
 pub struct CollisionPlugin;
 
@@ -91,6 +93,11 @@ impl YupBuffer {
 #[derive(Resource, Default, Deref, DerefMut)]
 struct YupEntities(pub Vec<Entity>);
 
+/// Any hazard each Yup in [`YupEntities`] is caught in, checked alongside their collision point so
+/// that both can be acted on together.
+#[derive(Resource, Default, Deref, DerefMut)]
+struct YupHazards(pub Vec<Option<HazardKind>>);
+
 #[derive(Resource)]
 struct CollisionsBufferBindGroup(BindGroup);
 
@@ -112,8 +119,14 @@ fn init(
         .spawn(Readback::buffer(collisions.clone()))
         .observe(
             |trigger: Trigger<ReadbackComplete>,
+             game: Option<Res<State<Game>>>,
              mut yups: Query<&mut CharacterState, With<Yup>>,
-             yup_entities: Res<YupEntities>| {
+             yup_entities: Res<YupEntities>,
+             yup_hazards: Res<YupHazards>| {
+                // Readbacks carry on while the level waits, but nothing should happen to the Yups.
+                if game.is_none_or(|game| *game.get() != Game::Playing) {
+                    return;
+                }
                 // This matches the type which was used to create the `ShaderStorageBuffer` above,
                 // and is a convenient way to interpret the data.
                 let collisions: Vec<u32> = trigger.event().to_shader_type();
@@ -121,10 +134,20 @@ fn init(
                 // Compare each value with a Yup entity id, and update said Yup's.
                 for (i, collision) in collisions.iter().enumerate() {
                     let entity = r!(yup_entities.get(i));
-                    let mut state = r!(yups.get_mut(*entity));
+                    // Yups are removed a little while after they die.
+                    let mut state = c!(yups.get_mut(*entity));
 
-                    if *collision == 1 {
-                        *state = CharacterState::Walking;
+                    if matches!(*state, CharacterState::Dead(_)) {
+                        // Nothing more can happen to them.
+                        continue;
+                    }
+                    if let Some(hazard) = yup_hazards.get(i).copied().flatten() {
+                        *state = CharacterState::Dead(hazard.death());
+                    } else if *collision == 1 {
+                        // Blockers stay put for as long as there's ground under their feet.
+                        if *state != CharacterState::Blocking {
+                            *state = CharacterState::Walking;
+                        }
                     } else {
                         *state = CharacterState::Falling;
                     }
@@ -138,6 +161,7 @@ fn init(
     // Also init an empty buffer for our Yup locations.
     commands.insert_resource(YupBuffer::default());
     commands.insert_resource(YupEntities::default());
+    commands.insert_resource(YupHazards::default());
 
     // Ensure sensibly-formatted render target image exists to initialise the compute pipeline.
     // These will get replaced once level loading begins in Screen::Intro.
@@ -318,35 +342,44 @@ impl render_graph::Node for CollisionsNode {
 }
 
 fn update_yup_locations(
-    level_transform: Query<&Transform, With<Level>>,
+    hazards: Query<&Hazard>,
+    level: Query<(&Level, &Transform)>,
+    time: Res<Time>,
     mut yup_buf: ResMut<YupBuffer>,
     mut yup_entities: ResMut<YupEntities>,
-    window: Single<&Window>,
-    yups: Query<(Entity, &Transform), With<Yup>>,
+    mut yup_hazards: ResMut<YupHazards>,
+    yups: Query<(Entity, &Transform, Has<Swimmer>), With<Yup>>,
 ) {
-    let lt = r!(level_transform.get_single());
+    let (level, lt) = r!(level.get_single());
     let mut entities: Vec<Entity> = vec![];
+    let mut caught: Vec<Option<HazardKind>> = vec![];
 
     // We need to pass
     //  - collision-point-x
     //  - collision-point-y
     //  - entity id
-    for (i, (yup, t)) in yups.iter().enumerate() {
+    for (i, (yup, t, swimmer)) in yups.iter().enumerate() {
         entities.push(yup);
-        let texture_pos = lt
+        // Hazards are checked at the same point as the terrain: the Yup's feet.
+        let hazard = hazard_at(&hazards, feet(t), time.elapsed_secs());
+        caught.push(hazard.filter(|h| !(swimmer && *h == HazardKind::Water)));
+
+        let mesh_pos = lt
             .compute_matrix()
             .inverse()
             .transform_point3(t.translation);
+        // Note the y-value inversion to convert from world pos.
+        let texture_pos = world_to_terrain(level.size, mesh_pos.truncate());
         yup_buf.yups[i] = Vec4::new(
             // Ordering the values like this just makes reading in the shader simpler (x is x, y ix
             // y, z is the id).
-            texture_pos.x + window.width(),
-            // Note the y-value inversion to convert from world pos.
-            -texture_pos.y + window.height() + YUP_FEET_FACTOR,
+            texture_pos.x,
+            texture_pos.y + YUP_FEET_FACTOR,
             yup.index() as f32,
             0.0, // Unused padding.
         );
     }
 
     *yup_entities = YupEntities(entities);
+    *yup_hazards = YupHazards(caught);
 }
@@ -1,6 +1,8 @@
+pub mod editor;
 pub mod ingame;
 pub mod intro;
 mod loading;
+mod options;
 mod splash;
 mod title;
 
@@ -11,9 +13,11 @@ pub fn plugin(app: &mut App) {
     app.enable_state_scoped_entities::<Screen>();
 
     app.add_plugins((
+        editor::plugin,
         loading::plugin,
         ingame::plugin,
         intro::plugin,
+        options::plugin,
         splash::plugin,
         title::plugin,
     ));
@@ -21,6 +25,7 @@ pub fn plugin(app: &mut App) {
 
 #[derive(States, Debug, Hash, PartialEq, Eq, Clone, Default)]
 pub enum Screen {
+    Editor,
     Loading,
     InGame,
     Intro,
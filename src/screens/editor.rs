@@ -0,0 +1,570 @@
+use bevy::{
+    image::TextureFormatPixelInfo,
+    picking::focus::HoverMap,
+    prelude::*,
+    render::{
+        gpu_readback::{Readback, ReadbackComplete},
+        renderer::RenderDevice,
+    },
+};
+use leafwing_input_manager::prelude::*;
+use tiny_bail::prelude::*;
+
+use crate::{
+    MainCamera,
+    assets::Levels,
+    game::{
+        hazards::HazardKind,
+        level::{
+            self, Brush, Level, LevelMaterial, LevelRenderTargets,
+            definition::{HazardArea, LevelDefinition, SteelArea},
+            terrain_to_world, world_to_terrain,
+        },
+        minimap,
+        steel::STEEL_COLOR,
+    },
+    input::GameAction,
+    physics::collision::YUP_COUNT,
+    screens::{Screen, intro::prepare_level_images},
+    ui::{BUTTON_BACKGROUND_COLOR, BUTTON_SELECTED_COLOR, Widgets, pointer_over_ui},
+    viewport::window_to_viewport,
+};
+
+// Area markers are see-through, so that the terrain underneath still shows.
+const AREA_MARKER_ALPHA: f32 = 0.6;
+// Drags smaller than this across are taken as stray clicks, rather than areas.
+const AREA_SIZE_MIN: f32 = 4.;
+const BRUSH_RADIUS_MAX: f32 = 80.;
+const BRUSH_RADIUS_MIN: f32 = 5.;
+const EXIT_MARKER_COLOR: Color = Color::srgb(0.95, 0.8, 0.2);
+const HATCH_MARKER_COLOR: Color = Color::srgb(0.3, 0.8, 0.3);
+const MARKER_SIZE: f32 = 24.;
+const PROBLEMS_COLOR: Color = Color::srgb(1., 0.4, 0.4);
+// The colour used when painting new terrain.
+const TERRAIN_COLOR: Color = Color::srgb_u8(143, 87, 62);
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<AreaStart>();
+    app.init_resource::<BrushRadius>();
+    app.init_resource::<EditorTool>();
+    app.add_systems(
+        OnEnter(Screen::Editor),
+        (
+            insert_editing_level,
+            prepare_level_images,
+            level::init,
+            minimap::spawn_minimap,
+            spawn_editor_ui,
+        )
+            .chain(),
+    );
+    app.add_systems(OnExit(Screen::Editor), remove_editing_level);
+    app.add_systems(
+        Update,
+        (
+            paint_terrain,
+            place_markers,
+            place_areas,
+            spawn_markers,
+            update_tool_buttons,
+            update_parameters_text,
+        )
+            .run_if(in_state(Screen::Editor)),
+    );
+    app.add_observer(on_terrain_read_back);
+}
+
+/// What happens when the designer clicks on the level.
+#[derive(Resource, Clone, Copy, Debug, Default, Eq, PartialEq)]
+enum EditorTool {
+    #[default]
+    Erase,
+    Paint,
+    Hatch,
+    Exit,
+    Hazard(HazardKind),
+    Steel,
+}
+
+/// Where the area being dragged out began, in terrain pixel coordinates.
+#[derive(Resource, Debug, Default, Deref, DerefMut)]
+struct AreaStart(Option<Vec2>);
+
+#[derive(Resource, Debug, Deref, DerefMut)]
+struct BrushRadius(f32);
+
+impl Default for BrushRadius {
+    fn default() -> Self {
+        Self(10.)
+    }
+}
+
+/// The definition being edited. This is written back to the level's asset when test-playing or
+/// saving.
+#[derive(Resource, Debug, Deref, DerefMut)]
+pub struct EditingLevel(LevelDefinition);
+
+#[derive(Component)]
+struct EditorMarker;
+
+#[derive(Component)]
+struct ParametersText;
+
+/// Why the level can't be test-played or saved yet, if it can't.
+#[derive(Component)]
+struct ProblemsText;
+
+#[derive(Component)]
+struct ToolButton(EditorTool);
+
+/// What to do with the terrain once it has been read back from the GPU.
+#[derive(Component, Clone, Copy, Debug)]
+enum TerrainReadback {
+    TestPlay,
+    #[cfg(not(target_arch = "wasm32"))]
+    Save,
+}
+
+fn insert_editing_level(
+    mut commands: Commands,
+    definitions: Res<Assets<LevelDefinition>>,
+    levels: Res<Levels>,
+) {
+    let definition = r!(definitions.get(&levels.first));
+    commands.insert_resource(EditingLevel(definition.clone()));
+}
+
+fn remove_editing_level(mut commands: Commands) {
+    commands.remove_resource::<EditingLevel>();
+}
+
+fn spawn_editor_ui(mut commands: Commands) {
+    commands
+        .spawn((
+            StateScoped(Screen::Editor),
+            Name::new("Editor Toolbar"),
+            Node {
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(6.),
+                flex_wrap: FlexWrap::Wrap,
+                padding: UiRect::all(Val::Px(6.)),
+                position_type: PositionType::Absolute,
+                row_gap: Val::Px(6.),
+                width: Val::Percent(100.),
+                ..default()
+            },
+        ))
+        .with_children(|p| {
+            for (label, tool) in [
+                ("Erase", EditorTool::Erase),
+                ("Paint", EditorTool::Paint),
+                ("Hatch", EditorTool::Hatch),
+                ("Exit", EditorTool::Exit),
+                ("Water", EditorTool::Hazard(HazardKind::Water)),
+                ("Lava", EditorTool::Hazard(HazardKind::Lava)),
+                ("Spikes", EditorTool::Hazard(HazardKind::Spikes)),
+                ("Crusher", EditorTool::Hazard(HazardKind::Crusher)),
+                ("Steel", EditorTool::Steel),
+            ] {
+                p.button(label).insert(ToolButton(tool)).observe(
+                    move |_ev: Trigger<Pointer<Click>>, mut current: ResMut<EditorTool>| {
+                        *current = tool;
+                    },
+                );
+            }
+
+            p.button("Brush -").observe(
+                |_ev: Trigger<Pointer<Click>>, mut radius: ResMut<BrushRadius>| {
+                    **radius = (**radius / 2.).max(BRUSH_RADIUS_MIN);
+                },
+            );
+            p.button("Brush +").observe(
+                |_ev: Trigger<Pointer<Click>>, mut radius: ResMut<BrushRadius>| {
+                    **radius = (**radius * 2.).min(BRUSH_RADIUS_MAX);
+                },
+            );
+
+            p.button("Yups -").observe(
+                |_ev: Trigger<Pointer<Click>>, mut editing: ResMut<EditingLevel>| {
+                    editing.yups = editing.yups.saturating_sub(1);
+                    editing.rescue_target = editing.rescue_target.min(editing.yups);
+                },
+            );
+            p.button("Yups +").observe(
+                |_ev: Trigger<Pointer<Click>>, mut editing: ResMut<EditingLevel>| {
+                    editing.yups = (editing.yups + 1).min(YUP_COUNT as u32);
+                },
+            );
+            p.button("Target -").observe(
+                |_ev: Trigger<Pointer<Click>>, mut editing: ResMut<EditingLevel>| {
+                    editing.rescue_target = editing.rescue_target.saturating_sub(1);
+                },
+            );
+            p.button("Target +").observe(
+                |_ev: Trigger<Pointer<Click>>, mut editing: ResMut<EditingLevel>| {
+                    editing.rescue_target = (editing.rescue_target + 1).min(editing.yups);
+                },
+            );
+            p.spawn((
+                Name::new("Parameters Text"),
+                ParametersText,
+                Text::default(),
+            ));
+
+            p.button("Test").observe(
+                |_ev: Trigger<Pointer<Click>>,
+                 mut commands: Commands,
+                 level_targets: Res<LevelRenderTargets>| {
+                    read_back_terrain(&mut commands, &level_targets, TerrainReadback::TestPlay);
+                },
+            );
+            #[cfg(not(target_arch = "wasm32"))]
+            p.button("Save").observe(
+                |_ev: Trigger<Pointer<Click>>,
+                 mut commands: Commands,
+                 level_targets: Res<LevelRenderTargets>| {
+                    read_back_terrain(&mut commands, &level_targets, TerrainReadback::Save);
+                },
+            );
+            p.button("Quit").observe(
+                |_ev: Trigger<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>| {
+                    next_screen.set(Screen::Title);
+                },
+            );
+            p.spawn((
+                Name::new("Problems Text"),
+                ProblemsText,
+                Text::default(),
+                TextColor(PROBLEMS_COLOR),
+                Node {
+                    width: Val::Percent(100.),
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn cursor_terrain_position(
+    camera: (&Camera, &GlobalTransform),
+    terrain_size: Vec2,
+    window: &Window,
+) -> Option<Vec2> {
+    let (cam, cam_transform) = camera;
+    let cursor_pos = window_to_viewport(cam, window.cursor_position()?);
+    let world_pos = cam.viewport_to_world_2d(cam_transform, cursor_pos).ok()?;
+    Some(world_to_terrain(terrain_size, world_pos))
+}
+
+fn paint_terrain(
+    action_state: Res<ActionState<GameAction>>,
+    brush_radius: Res<BrushRadius>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    hover_map: Res<HoverMap>,
+    level: Query<(&Level, &MeshMaterial2d<LevelMaterial>)>,
+    mut materials: ResMut<Assets<LevelMaterial>>,
+    nodes: Query<(), With<Node>>,
+    tool: Res<EditorTool>,
+    window: Single<&Window>,
+) {
+    let (level, material_handle) = rq!(level.get_single());
+    let brush = match *tool {
+        EditorTool::Erase => Brush::Erase,
+        EditorTool::Paint => Brush::Paint,
+        EditorTool::Hatch | EditorTool::Exit | EditorTool::Hazard(_) | EditorTool::Steel => {
+            Brush::None
+        }
+    };
+    let cursor_pos = cursor_terrain_position(*camera, level.size, &window);
+
+    let level_material = r!(materials.get_mut(&material_handle.0));
+    match cursor_pos {
+        Some(pos)
+            if action_state.pressed(&GameAction::Select)
+                && !pointer_over_ui(&hover_map, &nodes) =>
+        {
+            level_material.brush = brush as u32;
+            level_material.brush_color = TERRAIN_COLOR.into();
+            level_material.brush_radius = **brush_radius;
+            level_material.cursor_position = pos;
+        }
+        _ => level_material.brush = Brush::None as u32,
+    }
+}
+
+fn place_markers(
+    action_state: Res<ActionState<GameAction>>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut editing: ResMut<EditingLevel>,
+    hover_map: Res<HoverMap>,
+    level: Query<&Level>,
+    nodes: Query<(), With<Node>>,
+    tool: Res<EditorTool>,
+    window: Single<&Window>,
+) {
+    let placing = action_state.just_pressed(&GameAction::Select);
+    let removing = action_state.just_pressed(&GameAction::Remove);
+    if !(placing || removing) || pointer_over_ui(&hover_map, &nodes) {
+        return;
+    }
+
+    let level = rq!(level.get_single());
+    let pos = rq!(cursor_terrain_position(*camera, level.size, &window));
+    let markers = match *tool {
+        EditorTool::Hatch => &mut editing.hatches,
+        EditorTool::Exit => &mut editing.exits,
+        EditorTool::Erase | EditorTool::Paint | EditorTool::Hazard(_) | EditorTool::Steel => return,
+    };
+
+    if placing {
+        markers.push(pos);
+    } else {
+        // Removing takes out any marker under the cursor.
+        markers.retain(|marker| marker.distance(pos) > MARKER_SIZE / 2.);
+    }
+}
+
+// Hazards and steel are dragged out from one corner to the other.
+fn place_areas(
+    action_state: Res<ActionState<GameAction>>,
+    mut area_start: ResMut<AreaStart>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut editing: ResMut<EditingLevel>,
+    hover_map: Res<HoverMap>,
+    level: Query<&Level>,
+    nodes: Query<(), With<Node>>,
+    tool: Res<EditorTool>,
+    window: Single<&Window>,
+) {
+    if !matches!(*tool, EditorTool::Hazard(_) | EditorTool::Steel) {
+        if area_start.is_some() {
+            **area_start = None;
+        }
+        return;
+    }
+    let level = rq!(level.get_single());
+    let pos = rq!(cursor_terrain_position(*camera, level.size, &window));
+    // Areas can't stick out of the level.
+    let pos = pos.clamp(Vec2::ZERO, level.size);
+    let contains =
+        |position: Vec2, size: Vec2| Rect::from_corners(position, position + size).contains(pos);
+
+    if action_state.just_pressed(&GameAction::Remove) && !pointer_over_ui(&hover_map, &nodes) {
+        // Removing takes out any area of the current kind under the cursor.
+        match *tool {
+            EditorTool::Hazard(kind) => editing
+                .hazards
+                .retain(|h| h.kind != kind || !contains(h.position, h.size)),
+            _ => editing.steel.retain(|s| !contains(s.position, s.size)),
+        }
+        return;
+    }
+    if action_state.just_pressed(&GameAction::Select) && !pointer_over_ui(&hover_map, &nodes) {
+        **area_start = Some(pos);
+        return;
+    }
+    if !action_state.just_released(&GameAction::Select) {
+        return;
+    }
+
+    let start = rq!(area_start.take());
+    let position = start.min(pos);
+    let size = (start - pos).abs();
+    if size.min_element() < AREA_SIZE_MIN {
+        return;
+    }
+    match *tool {
+        EditorTool::Hazard(kind) => editing.hazards.push(HazardArea {
+            kind,
+            position,
+            size,
+        }),
+        _ => editing.steel.push(SteelArea { position, size }),
+    }
+}
+
+fn spawn_markers(
+    mut commands: Commands,
+    editing: Res<EditingLevel>,
+    level: Query<&Level>,
+    markers: Query<Entity, With<EditorMarker>>,
+) {
+    if !editing.is_changed() {
+        return;
+    }
+    let level = rq!(level.get_single());
+
+    for marker in &markers {
+        commands.entity(marker).despawn_recursive();
+    }
+
+    let hatches = editing
+        .hatches
+        .iter()
+        .map(|p| ("Hatch", HATCH_MARKER_COLOR, p));
+    let exits = editing.exits.iter().map(|p| ("Exit", EXIT_MARKER_COLOR, p));
+    for (name, color, pos) in hatches.chain(exits) {
+        commands.spawn((
+            Name::new(format!("{name} Marker")),
+            EditorMarker,
+            Sprite::from_color(color, Vec2::splat(MARKER_SIZE)),
+            Transform::from_translation(terrain_to_world(level.size, *pos).extend(2.)),
+            StateScoped(Screen::Editor),
+        ));
+    }
+
+    let hazards = editing
+        .hazards
+        .iter()
+        .map(|h| (format!("{:?}", h.kind), h.kind.color(), h.position, h.size));
+    let steel = editing
+        .steel
+        .iter()
+        .map(|s| ("Steel".to_string(), STEEL_COLOR, s.position, s.size));
+    for (name, color, position, size) in hazards.chain(steel) {
+        let centre = terrain_to_world(level.size, position + size / 2.);
+        commands.spawn((
+            Name::new(format!("{name} Marker")),
+            EditorMarker,
+            Sprite::from_color(color.with_alpha(AREA_MARKER_ALPHA), size),
+            Transform::from_translation(centre.extend(1.5)),
+            StateScoped(Screen::Editor),
+        ));
+    }
+}
+
+fn update_tool_buttons(
+    mut buttons: Query<(Ref<ToolButton>, &mut BackgroundColor)>,
+    tool: Res<EditorTool>,
+) {
+    if !tool.is_changed() && !buttons.iter().any(|(button, _)| button.is_added()) {
+        return;
+    }
+
+    for (button, mut background) in &mut buttons {
+        background.0 = if button.0 == *tool {
+            BUTTON_SELECTED_COLOR
+        } else {
+            BUTTON_BACKGROUND_COLOR
+        };
+    }
+}
+
+fn update_parameters_text(
+    brush_radius: Res<BrushRadius>,
+    editing: Res<EditingLevel>,
+    mut text: Single<&mut Text, With<ParametersText>>,
+) {
+    if !(editing.is_changed() || brush_radius.is_changed()) {
+        return;
+    }
+
+    text.0 = format!(
+        "Brush: {}px  Yups: {}  Target: {}",
+        **brush_radius, editing.yups, editing.rescue_target
+    );
+}
+
+fn read_back_terrain(
+    commands: &mut Commands,
+    level_targets: &LevelRenderTargets,
+    action: TerrainReadback,
+) {
+    commands.spawn((
+        Name::new("Terrain Readback"),
+        action,
+        Readback::texture(level_targets.destination.clone()),
+        StateScoped(Screen::Editor),
+    ));
+}
+
+// The terrain only exists on the GPU while editing, so we need to read it back before it can be
+// played or saved. Neither happens while the level has problems that `verify_levels` would fail it
+// for; those are listed instead.
+fn on_terrain_read_back(
+    trigger: Trigger<ReadbackComplete>,
+    #[cfg(not(target_arch = "wasm32"))] asset_server: Res<AssetServer>,
+    mut commands: Commands,
+    mut definitions: ResMut<Assets<LevelDefinition>>,
+    editing: Option<Res<EditingLevel>>,
+    mut images: ResMut<Assets<Image>>,
+    levels: Res<Levels>,
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut problems_text: Query<&mut Text, With<ProblemsText>>,
+    readbacks: Query<&TerrainReadback>,
+) {
+    let entity = trigger.entity();
+    let action = *rq!(readbacks.get(entity));
+    let editing = r!(editing);
+
+    // Readbacks repeat every frame for as long as the entity exists, and we only need the one.
+    commands.entity(entity).despawn();
+
+    let terrain = r!(images.get_mut(&editing.terrain_image));
+    let problems: Vec<String> = editing
+        .validate(terrain.size())
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    for mut text in &mut problems_text {
+        text.0 = problems.join("\n");
+    }
+    if !problems.is_empty() {
+        return;
+    }
+
+    // Rows of the texture are padded to satisfy wgpu's copy alignment, so strip that out.
+    let row_bytes = terrain.width() as usize * terrain.texture_descriptor.format.pixel_size();
+    let padded_row_bytes = RenderDevice::align_copy_bytes_per_row(row_bytes);
+    terrain.data = trigger
+        .event()
+        .0
+        .chunks(padded_row_bytes)
+        .flat_map(|row| &row[..row_bytes])
+        .copied()
+        .collect();
+    definitions.insert(&levels.first, editing.0.clone());
+
+    match action {
+        TerrainReadback::TestPlay => next_screen.set(Screen::Intro),
+        #[cfg(not(target_arch = "wasm32"))]
+        TerrainReadback::Save => {
+            save_level(&asset_server, &editing.0, terrain.clone(), &levels);
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_level(
+    asset_server: &AssetServer,
+    editing: &LevelDefinition,
+    terrain: Image,
+    levels: &Levels,
+) {
+    use std::path::Path;
+
+    const ASSETS_DIR: &str = "assets";
+
+    let definition_path = r!(asset_server.get_path(levels.first.id()));
+    let definition_path = Path::new(ASSETS_DIR).join(definition_path.path());
+    let terrain_path = Path::new(ASSETS_DIR).join(&editing.terrain);
+
+    match ron::ser::to_string_pretty(editing, ron::ser::PrettyConfig::default()) {
+        Ok(ron) => {
+            if let Err(e) = std::fs::write(&definition_path, ron) {
+                error!("Could not save {}: {e}", definition_path.display());
+            }
+        }
+        Err(e) => error!("Could not serialize level definition: {e}"),
+    }
+
+    match terrain.try_into_dynamic() {
+        Ok(image) => {
+            if let Err(e) = image.save(&terrain_path) {
+                error!("Could not save {}: {e}", terrain_path.display());
+            }
+        }
+        Err(e) => error!("Could not convert terrain for saving: {e}"),
+    }
+
+    info!("Saved level to {}", definition_path.display());
+}
@@ -0,0 +1,41 @@
+use bevy::prelude::*;
+
+use crate::{game::Game, screens::Screen, ui::Widgets};
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Game::Failed), spawn_failed_menu);
+}
+
+// Whatever went wrong is shown further down the screen, such as the timer's "Out of time!".
+fn spawn_failed_menu(mut commands: Commands) {
+    commands
+        .spawn((StateScoped(Game::Failed), Name::new("Failed Menu"), Node {
+            align_items: AlignItems::Center,
+            flex_direction: FlexDirection::Column,
+            height: Val::Percent(100.),
+            justify_content: JustifyContent::Start,
+            justify_self: JustifySelf::Center,
+            padding: UiRect::all(Val::Px(10.)),
+            row_gap: Val::Px(10.),
+            width: Val::Percent(100.),
+            ..default()
+        }))
+        .with_children(|p| {
+            p.spawn((Text::new("Level Failed"), TextFont {
+                font_size: 30.,
+                ..default()
+            }));
+
+            // Back through the briefing, which starts the level afresh.
+            p.button("Retry").observe(
+                |_ev: Trigger<Pointer<Click>>, mut screen: ResMut<NextState<Screen>>| {
+                    screen.set(Screen::Intro);
+                },
+            );
+            p.button("Quit").observe(
+                |_ev: Trigger<Pointer<Click>>, mut screen: ResMut<NextState<Screen>>| {
+                    screen.set(Screen::Title);
+                },
+            );
+        });
+}
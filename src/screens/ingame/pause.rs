@@ -1,23 +1,27 @@
-use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use bevy::prelude::*;
+use leafwing_input_manager::common_conditions::action_just_pressed;
 
-use crate::screens::Screen;
+use crate::{
+    GameSet,
+    config::Config,
+    input::GameAction,
+    screens::{Screen, options::spawn_options_menu},
+};
 
 use super::Game;
 
 pub fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Game::Paused), init);
 
-    // app.load_resource::<PlayingMusic>();
-    // app.add_systems(OnEnter(Screen::Playing), play_gameplay_music);
-    // app.add_systems(OnExit(Screen::Playing), stop_music);
-
     app.add_systems(
         Update,
-        pause.run_if(in_state(Game::Playing).and(input_just_pressed(KeyCode::Escape))),
+        pause
+            .in_set(GameSet::RecordInput)
+            .run_if(action_just_pressed(GameAction::Pause)),
     );
     app.add_systems(
         Update,
-        unpause.run_if(in_state(Game::Paused).and(input_just_pressed(KeyCode::Escape))),
+        unpause.run_if(in_state(Game::Paused).and(action_just_pressed(GameAction::Pause))),
     );
 }
 
@@ -73,6 +77,22 @@ fn init(mut commands: Commands) {
                     next_state.set(Game::Playing);
                 },
             );
+
+            p.spawn((Name::new("Options Button"), Button, Node {
+                align_items: AlignItems::Center,
+                height: Val::Px(65.0),
+                justify_content: JustifyContent::Center,
+                width: Val::Px(200.0),
+                ..default()
+            }))
+            .with_children(|p| {
+                p.spawn((Name::new("Button Text"), Text::new("options")));
+            })
+            .observe(
+                |_ev: Trigger<Pointer<Click>>, mut commands: Commands, config: Res<Config>| {
+                    spawn_options_menu(&mut commands, &config).insert(StateScoped(Game::Paused));
+                },
+            );
         });
 }
 
@@ -80,41 +100,6 @@ fn init(mut commands: Commands) {
 // commands.queue(spawn_level_command);
 // }
 
-// #[derive(Resource, Asset, Reflect, Clone)]
-// pub struct PlayingMusic {
-//     #[dependency]
-//     handle: Handle<AudioSource>,
-//     entity: Option<Entity>,
-// }
-//
-// impl FromWorld for PlayingMusic {
-//     fn from_world(world: &mut World) -> Self {
-//         let assets = world.resource::<AssetServer>();
-//         Self {
-//             handle: assets.load("audio/music/Fluffing A Duck.ogg"),
-//             entity: None,
-//         }
-//     }
-// }
-//
-// fn play_gameplay_music(mut commands: Commands, mut music: ResMut<PlayingMusic>) {
-//     music.entity = Some(
-//         commands
-//             .spawn((
-//                 AudioPlayer(music.handle.clone()),
-//                 PlaybackSettings::LOOP,
-//                 Music,
-//             ))
-//             .id(),
-//     );
-// }
-//
-// fn stop_music(mut commands: Commands, mut music: ResMut<PlayingMusic>) {
-//     if let Some(entity) = music.entity.take() {
-//         commands.entity(entity).despawn_recursive();
-//     }
-// }
-
 fn pause(mut game: ResMut<NextState<Game>>) {
     game.set(Game::Paused);
 }
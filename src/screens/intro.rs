@@ -6,50 +6,104 @@ use tiny_bail::prelude::*;
 
 use crate::{
     assets::Levels,
-    game::{Game, level::LevelRenderTargets},
+    dialogue::{self, Dialogue, StartDialogue},
+    game::{
+        Game,
+        level::{LevelRenderTargets, definition::LevelDefinition},
+        skills::Skill,
+    },
     screens::Screen,
-    ui::Containers,
+    ui::{Containers, Widgets, format_time},
 };
 
-const INTRO_TIMER_DURATION_SECS: f32 = 0.5;
+// How wide the preview of the level is, in logical pixels.
+const THUMBNAIL_WIDTH: f32 = 320.;
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(
         OnEnter(Screen::Intro),
-        (spawn_intro_screen, insert_intro_timer, prepare_level_images).chain(),
+        (spawn_briefing, prepare_level_images, start_intro_dialogue).chain(),
     );
-    app.add_systems(OnExit(Screen::Intro), remove_intro_timer);
     app.add_systems(
         Update,
-        (tick_intro_timer, check_intro_timer)
-            .chain()
-            .run_if(in_state(Screen::Intro)),
+        show_start_button
+            // The level's story comes first. Ordered after it starts, so that the dialogue is
+            // already there to hold the button back on the first frame.
+            .after(dialogue::start_dialogue)
+            .run_if(in_state(Screen::Intro).and(not(resource_exists::<Dialogue>))),
     );
 }
 
-#[derive(Resource, Debug, Clone, PartialEq, Reflect)]
-#[reflect(Resource)]
-struct IntroTimer(Timer);
+/// Hidden until any intro dialogue is over, so that clicking through it can't start the level.
+#[derive(Component, Debug)]
+struct StartButton;
 
-impl Default for IntroTimer {
-    fn default() -> Self {
-        Self(Timer::from_seconds(
-            INTRO_TIMER_DURATION_SECS,
-            TimerMode::Once,
-        ))
-    }
-}
+fn spawn_briefing(
+    mut commands: Commands,
+    definitions: Res<Assets<LevelDefinition>>,
+    images: Res<Assets<Image>>,
+    levels: Res<Levels>,
+) {
+    let definition = r!(definitions.get(&levels.first));
+    let terrain_size = r!(images.get(&definition.terrain_image)).size_f32();
+
+    let time_limit = match definition.time_limit_secs {
+        Some(secs) => format!("Time limit: {}", format_time(secs)),
+        None => "No time limit".to_string(),
+    };
+    let skills = Skill::ALL
+        .into_iter()
+        .filter_map(|skill| match definition.skill_count(skill) {
+            None => Some(skill.label().to_string()),
+            Some(0) => None,
+            Some(count) => Some(format!("{} x{count}", skill.label())),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
 
-fn spawn_intro_screen(mut commands: Commands) {
     commands
         .ui_root()
-        .insert(StateScoped(Screen::Intro))
+        .insert((Name::new("Briefing"), StateScoped(Screen::Intro)))
         .with_children(|p| {
-            p.spawn(Text::new("Intro..."));
+            p.spawn((Text::new(definition.name.clone()), TextFont {
+                font_size: 30.,
+                ..default()
+            }));
+            p.spawn(Text::new(format!("by {}", definition.designer)));
+            p.spawn((
+                Name::new("Thumbnail"),
+                ImageNode::new(definition.terrain_image.clone()),
+                Node {
+                    height: Val::Px(THUMBNAIL_WIDTH * terrain_size.y / terrain_size.x),
+                    width: Val::Px(THUMBNAIL_WIDTH),
+                    ..default()
+                },
+            ));
+            p.spawn(Text::new(format!(
+                "{} Yups, {} to rescue",
+                definition.yups, definition.rescue_target
+            )));
+            p.spawn(Text::new(format!(
+                "Release rate: {}",
+                definition.release_rate
+            )));
+            p.spawn(Text::new(time_limit));
+            p.spawn(Text::new(format!("Skills: {skills}")));
+            p.button("Start")
+                .insert((StartButton, Visibility::Hidden))
+                .observe(
+                    |_ev: Trigger<Pointer<Click>>,
+                     mut next_screen: ResMut<NextState<Screen>>,
+                     mut next_game_state: ResMut<NextState<Game>>| {
+                        next_screen.set(Screen::InGame);
+                        next_game_state.set(Game::Playing);
+                    },
+                );
         });
 }
 
 pub fn prepare_level_images(
+    definitions: Res<Assets<LevelDefinition>>,
     mut images: ResMut<Assets<Image>>,
     mut level_targets: ResMut<LevelRenderTargets>,
     levels: Res<Levels>,
@@ -58,40 +112,38 @@ pub fn prepare_level_images(
     // be modified in order to use the image as a render target. Here, we create two copies of the
     // level image: one to use as "source", the other "destination". These will be swapped after
     // rendering each frame.
-    let level_image = r!(images.get_mut(&levels.level.clone()));
+    let definition = r!(definitions.get(&levels.first));
+    let level_image = r!(images.get_mut(&definition.terrain_image));
     let mut source_image = level_image.clone();
-    source_image.texture_descriptor.usage =
-        TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING | TextureUsages::RENDER_ATTACHMENT;
+    // COPY_SRC allows the editor to read back the edited terrain.
+    source_image.texture_descriptor.usage = TextureUsages::COPY_DST
+        | TextureUsages::COPY_SRC
+        | TextureUsages::TEXTURE_BINDING
+        | TextureUsages::RENDER_ATTACHMENT;
     let destination_image = source_image.clone();
 
     // Update the original image with new usage settings, and add the second copy of it to image assets.
-    images.insert(&levels.level.clone(), source_image);
+    images.insert(&definition.terrain_image, source_image);
 
     // Store both image handles on our resource to facilitate swapping them each frame, and so the
     // material spawning system can grab them easily in the next screen.
-    level_targets.source = levels.level.clone();
+    level_targets.source = definition.terrain_image.clone();
     level_targets.destination = images.add(destination_image);
 }
 
-fn insert_intro_timer(mut commands: Commands) {
-    commands.init_resource::<IntroTimer>();
-}
-
-fn remove_intro_timer(mut commands: Commands) {
-    commands.remove_resource::<IntroTimer>();
-}
-
-fn tick_intro_timer(time: Res<Time>, mut timer: ResMut<IntroTimer>) {
-    timer.0.tick(time.delta());
+fn start_intro_dialogue(
+    definitions: Res<Assets<LevelDefinition>>,
+    mut dialogue: EventWriter<StartDialogue>,
+    levels: Res<Levels>,
+) {
+    let definition = r!(definitions.get(&levels.first));
+    if !definition.intro.is_empty() {
+        dialogue.send(StartDialogue(definition.intro.clone()));
+    }
 }
 
-fn check_intro_timer(
-    timer: ResMut<IntroTimer>,
-    mut next_screen: ResMut<NextState<Screen>>,
-    mut next_game_state: ResMut<NextState<Game>>,
-) {
-    if timer.0.just_finished() {
-        next_screen.set(Screen::InGame);
-        next_game_state.set(Game::Playing);
+fn show_start_button(mut buttons: Query<&mut Visibility, With<StartButton>>) {
+    for mut visibility in &mut buttons {
+        visibility.set_if_neq(Visibility::Inherited);
     }
 }
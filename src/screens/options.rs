@@ -0,0 +1,360 @@
+use bevy::{
+    ecs::system::EntityCommands, input::InputSystem, prelude::*, ui::RelativeCursorPosition,
+};
+use leafwing_input_manager::{plugin::InputManagerSystem, prelude::*};
+use tiny_bail::prelude::*;
+
+use crate::{
+    config::{Config, KeyAction},
+    input::GameAction,
+    ui::{BUTTON_BACKGROUND_COLOR, BUTTON_SELECTED_COLOR, Widgets},
+    viewport::ViewportScaling,
+};
+
+const OVERLAY_COLOR: Color = Color::srgba(0.05, 0.05, 0.08, 0.95);
+const SLIDER_HEIGHT: f32 = 16.;
+const SLIDER_WIDTH: f32 = 200.;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Rebinding>();
+    app.init_resource::<UnsavedChanges>();
+    app.add_observer(reset_rebinding);
+    app.add_observer(save_config);
+    // Grab the key before anything else can react to it, otherwise binding Escape would also
+    // unpause the game.
+    app.add_systems(
+        PreUpdate,
+        capture_rebinding
+            .after(InputSystem)
+            .before(InputManagerSystem::Update)
+            .run_if(any_with_component::<OptionsMenu>),
+    );
+    app.add_systems(
+        Update,
+        (
+            drag_sliders,
+            update_labels.run_if(resource_changed::<Config>.or(resource_changed::<Rebinding>)),
+            update_slider_fills.run_if(resource_changed::<Config>),
+            mark_unsaved.run_if(resource_changed::<Config>),
+        )
+            .chain()
+            .run_if(any_with_component::<OptionsMenu>),
+    );
+}
+
+#[derive(Component, Debug)]
+pub struct OptionsMenu;
+
+/// The action waiting for a key press to bind to it, if any.
+#[derive(Resource, Debug, Default, PartialEq)]
+struct Rebinding(Option<KeyAction>);
+
+#[derive(Component, Debug)]
+struct RebindButton(KeyAction);
+
+/// Whether the config has changed since it was last saved.
+#[derive(Resource, Debug, Default)]
+struct UnsavedChanges(bool);
+
+#[derive(Clone, Copy, Debug)]
+enum Setting {
+    Fullscreen,
+    Scaling,
+    Vsync,
+    PixelPerfect,
+}
+
+impl Setting {
+    const ALL: [Self; 4] = [
+        Self::Fullscreen,
+        Self::Scaling,
+        Self::Vsync,
+        Self::PixelPerfect,
+    ];
+
+    fn label(&self, config: &Config) -> String {
+        let on_off = |on| if on { "on" } else { "off" };
+        let window = &config.window;
+        match self {
+            Self::Fullscreen => format!("Fullscreen: {}", on_off(window.fullscreen)),
+            Self::Scaling => format!("Scaling: {}", match window.scaling {
+                ViewportScaling::Fit => "fit to window",
+                ViewportScaling::Integer => "whole pixels",
+            }),
+            Self::Vsync => format!("VSync: {}", on_off(window.vsync)),
+            Self::PixelPerfect => format!("Pixel perfect zoom: {}", on_off(window.pixel_perfect)),
+        }
+    }
+
+    fn toggle(&self, config: &mut Config) {
+        let window = &mut config.window;
+        match self {
+            Self::Fullscreen => window.fullscreen = !window.fullscreen,
+            Self::Scaling => {
+                window.scaling = match window.scaling {
+                    ViewportScaling::Fit => ViewportScaling::Integer,
+                    ViewportScaling::Integer => ViewportScaling::Fit,
+                }
+            }
+            Self::Vsync => window.vsync = !window.vsync,
+            Self::PixelPerfect => window.pixel_perfect = !window.pixel_perfect,
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+struct SettingButton(Setting);
+
+#[derive(Clone, Copy, Debug)]
+enum VolumeChannel {
+    Master,
+    Music,
+    Sfx,
+}
+
+impl VolumeChannel {
+    const ALL: [Self; 3] = [Self::Master, Self::Music, Self::Sfx];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Master => "Master",
+            Self::Music => "Music",
+            Self::Sfx => "Effects",
+        }
+    }
+
+    fn volume(&self, config: &Config) -> f32 {
+        match self {
+            Self::Master => config.audio.master_volume,
+            Self::Music => config.audio.music_volume,
+            Self::Sfx => config.audio.sfx_volume,
+        }
+    }
+
+    fn volume_mut<'a>(&self, config: &'a mut Config) -> &'a mut f32 {
+        match self {
+            Self::Master => &mut config.audio.master_volume,
+            Self::Music => &mut config.audio.music_volume,
+            Self::Sfx => &mut config.audio.sfx_volume,
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+struct VolumeSlider(VolumeChannel);
+
+#[derive(Component, Debug)]
+struct SliderFill;
+
+fn binding_label(action: KeyAction, config: &Config, rebinding: &Rebinding) -> String {
+    if rebinding.0 == Some(action) {
+        format!("{}: press a key...", action.label())
+    } else {
+        format!("{}: {:?}", action.label(), config.keys.get(action))
+    }
+}
+
+/// Spawns the options menu over whatever is on screen. Callers should scope it to their own state,
+/// so it goes away along with the menu it was opened from.
+pub fn spawn_options_menu<'a>(commands: &'a mut Commands, config: &Config) -> EntityCommands<'a> {
+    let mut menu = commands.spawn((
+        Name::new("Options Menu"),
+        OptionsMenu,
+        BackgroundColor(OVERLAY_COLOR),
+        // Above the menu it was opened from.
+        GlobalZIndex(1),
+        Node {
+            align_items: AlignItems::Center,
+            flex_direction: FlexDirection::Column,
+            height: Val::Percent(100.),
+            justify_content: JustifyContent::Center,
+            position_type: PositionType::Absolute,
+            row_gap: Val::Px(8.),
+            width: Val::Percent(100.),
+            ..default()
+        },
+    ));
+    menu.with_children(|p| {
+        p.spawn((Text::new("Options"), TextFont {
+            font_size: 30.,
+            ..default()
+        }));
+
+        for channel in VolumeChannel::ALL {
+            p.spawn((Name::new("Volume Row"), Node {
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(10.),
+                ..default()
+            }))
+            .with_children(|p| {
+                p.spawn((Text::new(channel.label()), Node {
+                    width: Val::Px(80.),
+                    ..default()
+                }));
+                p.spawn((
+                    Name::new("Volume Slider"),
+                    VolumeSlider(channel),
+                    BackgroundColor(BUTTON_BACKGROUND_COLOR),
+                    Node {
+                        height: Val::Px(SLIDER_HEIGHT),
+                        width: Val::Px(SLIDER_WIDTH),
+                        ..default()
+                    },
+                    RelativeCursorPosition::default(),
+                ))
+                .with_children(|p| {
+                    p.spawn((
+                        Name::new("Slider Fill"),
+                        SliderFill,
+                        BackgroundColor(BUTTON_SELECTED_COLOR),
+                        // Let clicks through to the slider itself.
+                        PickingBehavior::IGNORE,
+                        Node {
+                            height: Val::Percent(100.),
+                            width: Val::Percent(channel.volume(config) * 100.),
+                            ..default()
+                        },
+                    ));
+                });
+            });
+        }
+
+        for setting in Setting::ALL {
+            p.button(setting.label(config))
+                .insert(SettingButton(setting))
+                .observe(toggle_setting);
+        }
+
+        p.spawn(Text::new("Controls"));
+        for action in KeyAction::ALL {
+            p.button(binding_label(action, config, &Rebinding::default()))
+                .insert(RebindButton(action))
+                .observe(start_rebinding);
+        }
+
+        p.button("Back").observe(close_options_menu);
+    });
+    menu
+}
+
+fn reset_rebinding(_trigger: Trigger<OnAdd, OptionsMenu>, mut rebinding: ResMut<Rebinding>) {
+    rebinding.set_if_neq(Rebinding(None));
+}
+
+fn toggle_setting(
+    trigger: Trigger<Pointer<Click>>,
+    buttons: Query<&SettingButton>,
+    mut config: ResMut<Config>,
+) {
+    let SettingButton(setting) = r!(buttons.get(trigger.entity()));
+    setting.toggle(&mut config);
+}
+
+fn start_rebinding(
+    trigger: Trigger<Pointer<Click>>,
+    buttons: Query<&RebindButton>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    let RebindButton(action) = r!(buttons.get(trigger.entity()));
+    rebinding.0 = Some(*action);
+}
+
+fn close_options_menu(
+    _trigger: Trigger<Pointer<Click>>,
+    mut commands: Commands,
+    menus: Query<Entity, With<OptionsMenu>>,
+) {
+    for menu in &menus {
+        commands.entity(menu).despawn_recursive();
+    }
+}
+
+fn capture_rebinding(
+    mut config: ResMut<Config>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    let action = rq!(rebinding.0);
+    let key = rq!(keys.get_just_pressed().next().copied());
+    // Forget the key was pressed at all, so no action sees it.
+    keys.reset(key);
+    config.keys.rebind(action, key);
+    rebinding.0 = None;
+}
+
+fn drag_sliders(
+    action_state: Res<ActionState<GameAction>>,
+    mut config: ResMut<Config>,
+    sliders: Query<(&VolumeSlider, &RelativeCursorPosition)>,
+) {
+    if !action_state.pressed(&GameAction::Select) {
+        return;
+    }
+
+    for (VolumeSlider(channel), cursor) in &sliders {
+        if !cursor.mouse_over() {
+            continue;
+        }
+        let normalized = r!(cursor.normalized);
+        let volume = normalized.x.clamp(0., 1.);
+        // Holding the button still shouldn't keep saving the config.
+        if channel.volume(&config) != volume {
+            *channel.volume_mut(&mut config) = volume;
+        }
+    }
+}
+
+fn update_labels(
+    config: Res<Config>,
+    rebind_buttons: Query<(&RebindButton, &Children)>,
+    rebinding: Res<Rebinding>,
+    setting_buttons: Query<(&SettingButton, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    let settings = setting_buttons
+        .iter()
+        .map(|(SettingButton(setting), children)| (setting.label(&config), children));
+    let bindings = rebind_buttons
+        .iter()
+        .map(|(RebindButton(action), children)| {
+            (binding_label(*action, &config, &rebinding), children)
+        });
+    for (label, children) in settings.chain(bindings) {
+        let mut texts = texts.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            text.0.clone_from(&label);
+        }
+    }
+}
+
+fn update_slider_fills(
+    config: Res<Config>,
+    mut fills: Query<&mut Node, With<SliderFill>>,
+    sliders: Query<(&VolumeSlider, &Children)>,
+) {
+    for (VolumeSlider(channel), children) in &sliders {
+        let mut fills = fills.iter_many_mut(children);
+        while let Some(mut node) = fills.fetch_next() {
+            node.width = Val::Percent(channel.volume(&config) * 100.);
+        }
+    }
+}
+
+fn mark_unsaved(mut unsaved: ResMut<UnsavedChanges>) {
+    unsaved.0 = true;
+}
+
+// Every change made in the menu is kept, there's no separate "apply" step. Saving waits for the
+// menu to close, rather than writing the file every frame a slider is dragged.
+fn save_config(
+    _trigger: Trigger<OnRemove, OptionsMenu>,
+    config: Res<Config>,
+    mut unsaved: ResMut<UnsavedChanges>,
+) {
+    if !std::mem::take(&mut unsaved.0) {
+        return;
+    }
+    if let Err(e) = config.save() {
+        warn!("{e}");
+    }
+}
@@ -1,10 +1,11 @@
 use bevy::{
     image::{ImageLoaderSettings, ImageSampler},
-    input::common_conditions::input_just_pressed,
     prelude::*,
 };
 
-use crate::{NonGameSet, screens::Screen, ui::Containers};
+use leafwing_input_manager::common_conditions::action_just_pressed;
+
+use crate::{NonGameSet, config::Config, input::GameAction, screens::Screen, ui::Containers};
 
 pub fn plugin(app: &mut App) {
     app.insert_resource(ClearColor(SPLASH_BACKGROUND_COLOR));
@@ -31,15 +32,17 @@ pub fn plugin(app: &mut App) {
     app.add_systems(
         Update,
         continue_to_loading_screen
-            .run_if(input_just_pressed(KeyCode::Escape).and(in_state(Screen::Splash))),
+            .run_if(action_just_pressed(GameAction::SkipSplash).and(in_state(Screen::Splash))),
     );
 }
 
 const SPLASH_BACKGROUND_COLOR: Color = Color::srgb(0.157, 0.157, 0.157);
-const SPLASH_DURATION_SECS: f32 = 1.8;
-const SPLASH_FADE_DURATION_SECS: f32 = 0.6;
 
-fn spawn_splash_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn spawn_splash_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    config: Res<Config>,
+) {
     commands
         .ui_root()
         .insert((
@@ -64,8 +67,8 @@ fn spawn_splash_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
                     },
                 )),
                 ImageNodeFadeInOut {
-                    total_duration: SPLASH_DURATION_SECS,
-                    fade_duration: SPLASH_FADE_DURATION_SECS,
+                    total_duration: config.timers.splash_secs,
+                    fade_duration: config.timers.splash_fade_secs,
                     t: 0.0,
                 },
             ));
@@ -107,14 +110,11 @@ fn apply_fade_in_out(mut animation_query: Query<(&ImageNodeFadeInOut, &mut Image
 #[reflect(Resource)]
 struct SplashTimer(Timer);
 
-impl Default for SplashTimer {
-    fn default() -> Self {
-        Self(Timer::from_seconds(SPLASH_DURATION_SECS, TimerMode::Once))
-    }
-}
-
-fn insert_splash_timer(mut commands: Commands) {
-    commands.init_resource::<SplashTimer>();
+fn insert_splash_timer(mut commands: Commands, config: Res<Config>) {
+    commands.insert_resource(SplashTimer(Timer::from_seconds(
+        config.timers.splash_secs,
+        TimerMode::Once,
+    )));
 }
 
 fn remove_splash_timer(mut commands: Commands) {
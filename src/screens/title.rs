@@ -1,14 +1,15 @@
 use bevy::prelude::*;
 
-use crate::screens::Screen;
+use crate::{
+    config::Config,
+    screens::{Screen, options::spawn_options_menu},
+    ui::Widgets,
+};
 
 pub fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Screen::Title), spawn_title_screen);
 }
 
-#[derive(Component)]
-pub struct Button;
-
 fn spawn_title_screen(mut commands: Commands) {
     commands
         .spawn((StateScoped(Screen::Title), Name::new("Title"), Node {
@@ -18,6 +19,7 @@ fn spawn_title_screen(mut commands: Commands) {
             justify_content: JustifyContent::Start,
             justify_self: JustifySelf::Center,
             padding: UiRect::all(Val::Px(10.)),
+            row_gap: Val::Px(10.),
             width: Val::Percent(100.),
             ..default()
         }))
@@ -27,20 +29,20 @@ fn spawn_title_screen(mut commands: Commands) {
                 ..default()
             }));
 
-            p.spawn((Name::new("Start Button"), Button, Node {
-                align_items: AlignItems::Center,
-                height: Val::Px(65.0),
-                justify_content: JustifyContent::Center,
-                width: Val::Px(200.0),
-                ..default()
-            }))
-            .with_children(|p| {
-                p.spawn((Name::new("Button Text"), Text::new("Start")));
-            })
-            .observe(
+            p.button("Start").observe(
                 |_ev: Trigger<Pointer<Click>>, mut next_screen_state: ResMut<NextState<Screen>>| {
                     next_screen_state.set(Screen::Intro);
                 },
             );
+            p.button("Editor").observe(
+                |_ev: Trigger<Pointer<Click>>, mut next_screen_state: ResMut<NextState<Screen>>| {
+                    next_screen_state.set(Screen::Editor);
+                },
+            );
+            p.button("Options").observe(
+                |_ev: Trigger<Pointer<Click>>, mut commands: Commands, config: Res<Config>| {
+                    spawn_options_menu(&mut commands, &config).insert(StateScoped(Screen::Title));
+                },
+            );
         });
 }
@@ -1,4 +1,9 @@
-use bevy::{ecs::system::EntityCommands, prelude::*, ui::Val::*};
+use bevy::{
+    ecs::system::EntityCommands,
+    picking::{focus::HoverMap, pointer::PointerId},
+    prelude::*,
+    ui::Val::*,
+};
 
 /// An extension trait for spawning UI containers.
 pub trait Containers {
@@ -21,3 +26,55 @@ impl Containers for Commands<'_, '_> {
         }))
     }
 }
+
+/// An extension trait for spawning UI widgets.
+pub trait Widgets {
+    /// Spawns a button with a text label. Attach an observer to do something when it's clicked.
+    fn button(&mut self, text: impl Into<String>) -> EntityCommands;
+}
+
+impl Widgets for ChildBuilder<'_> {
+    fn button(&mut self, text: impl Into<String>) -> EntityCommands {
+        let text = text.into();
+        let mut button = self.spawn((
+            Name::new(format!("{text} Button")),
+            Button,
+            BackgroundColor(BUTTON_BACKGROUND_COLOR),
+            Node {
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                padding: UiRect::axes(Px(12.0), Px(6.0)),
+                ..default()
+            },
+        ));
+        button.with_children(|p| {
+            p.spawn((Name::new("Button Text"), Text::new(text)));
+        });
+        button
+    }
+}
+
+pub const BUTTON_BACKGROUND_COLOR: Color = Color::srgb(0.2, 0.2, 0.2);
+pub const BUTTON_SELECTED_COLOR: Color = Color::srgb(0.35, 0.45, 0.25);
+
+/// Formats a number of seconds as minutes and seconds, such as "2:05".
+pub fn format_time(secs: f32) -> String {
+    let secs = secs.max(0.).ceil() as u32;
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+/// Whether the mouse is over any UI node, in which case clicks shouldn't reach the level.
+pub fn pointer_over_ui(hover_map: &HoverMap, nodes: &Query<(), With<Node>>) -> bool {
+    pointer_id_over_ui(hover_map, nodes, PointerId::Mouse)
+}
+
+/// Like [`pointer_over_ui`], for any pointer, such as a finger on a touch screen.
+pub fn pointer_id_over_ui(
+    hover_map: &HoverMap,
+    nodes: &Query<(), With<Node>>,
+    pointer: PointerId,
+) -> bool {
+    hover_map
+        .get(&pointer)
+        .is_some_and(|hits| hits.keys().any(|e| nodes.contains(*e)))
+}
@@ -0,0 +1,87 @@
+use bevy::{
+    prelude::*,
+    render::camera::{CameraUpdateSystem, Viewport},
+    ui::UiSystem,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::MainCamera;
+
+/// The resolution the game is designed for. The main camera always shows this many world units
+/// (before zooming), and the UI is laid out as if the window were this size. Whatever the actual
+/// window size, the view is scaled to fit and letterboxed.
+pub const VIRTUAL_RESOLUTION: Vec2 = Vec2::new(1280., 720.);
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<ViewportScaling>();
+    app.add_systems(
+        PostUpdate,
+        fit_viewport
+            .before(CameraUpdateSystem)
+            .before(UiSystem::Layout),
+    );
+}
+
+/// How the virtual resolution is scaled up (or down) to fill the window.
+#[derive(Resource, Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum ViewportScaling {
+    /// Use as much of the window as possible, at the cost of uneven pixel sizes.
+    #[default]
+    Fit,
+    /// Scale by whole numbers only, so every pixel is the same size. Leaves wider borders.
+    Integer,
+}
+
+impl ViewportScaling {
+    /// The factor by which to scale the virtual resolution to fit a window of the given physical
+    /// size.
+    pub fn scale(&self, window_size: Vec2) -> f32 {
+        let fit = (window_size / VIRTUAL_RESOLUTION).min_element();
+        match self {
+            Self::Fit => fit,
+            // Below 1x there's no whole number to choose, so fall back to fitting.
+            Self::Integer if fit < 1. => fit,
+            Self::Integer => fit.floor(),
+        }
+    }
+}
+
+/// Converts a position in the window, such as the mouse cursor's, to one within the main camera's
+/// viewport, which is letterboxed within the window.
+pub fn window_to_viewport(camera: &Camera, window_position: Vec2) -> Vec2 {
+    let viewport_min = camera
+        .logical_viewport_rect()
+        .map_or(Vec2::ZERO, |rect| rect.min);
+    window_position - viewport_min
+}
+
+fn fit_viewport(
+    mut camera: Single<&mut Camera, With<MainCamera>>,
+    scaling: Res<ViewportScaling>,
+    mut ui_scale: ResMut<UiScale>,
+    window: Single<Ref<Window>>,
+) {
+    if !window.is_changed() && !scaling.is_changed() {
+        return;
+    }
+
+    let window_size = window.physical_size().as_vec2();
+    if window_size.min_element() <= 0. {
+        // Minimised, there's nothing to fit.
+        return;
+    }
+
+    let scale = scaling.scale(window_size);
+    let viewport_size = (VIRTUAL_RESOLUTION * scale).round();
+    // Centre the view, leaving equal borders on either side.
+    let viewport_position = ((window_size - viewport_size) / 2.).floor();
+    camera.viewport = Some(Viewport {
+        physical_position: viewport_position.as_uvec2(),
+        physical_size: viewport_size.as_uvec2(),
+        ..default()
+    });
+
+    // UI is laid out in logical pixels, so scale it to match the view.
+    ui_scale.0 = scale / window.scale_factor();
+}